@@ -0,0 +1,313 @@
+/* befunge/builder.rs - Contains a builder for the Befunge-93 interpreter
+ * Copyright 2018 Arnav Borborah
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{self, BufRead, Write};
+
+// Throughout comments, befunge::Error will be referred to as BefungeError
+use super::error::Error as BefungeError;
+use super::interpreter::Interpreter;
+use super::options::Options;
+use super::playfield::{Coord, Direction};
+
+/// Builds an `Interpreter` one setting at a time, instead of passing every
+/// argument of `Interpreter::with_options` positionally. Settings that are not
+/// given keep the same defaults as `Interpreter::new`: the program counter starts
+/// at (0, 0) moving right, output is discarded, and there is no input.
+///
+/// ```
+/// use bef93::befunge::{Coord, Direction, InterpreterBuilder};
+///
+/// let mut output = Vec::new();
+/// {
+///     let mut interpreter = InterpreterBuilder::new()
+///         .code("@.+<\n   2\n   3")
+///         .output(&mut output)
+///         .position(Coord { x: 3, y: 2 })
+///         .direction(Direction::Up)
+///         .seed(42)
+///         .build()
+///         .unwrap();
+///
+///     interpreter.execute().unwrap();
+/// }
+///
+/// assert_eq!(output, b"5 ");
+/// ```
+pub struct InterpreterBuilder<Writable, Readable>
+where
+    Writable: Write,
+    Readable: BufRead,
+{
+    code: String,
+    output_handle: Writable,
+    input_handle: Readable,
+    program_counter_position: Option<Coord>,
+    program_counter_direction: Option<Direction>,
+    // Kept apart from `options`, so they apply whether they are set before or
+    // after `options`
+    seed: Option<u64>,
+    initial_stack: Option<Vec<i64>>,
+    options: Options,
+}
+
+impl InterpreterBuilder<io::Sink, io::Empty> {
+    // Starts a builder for an empty program with the default settings
+    pub fn new() -> InterpreterBuilder<io::Sink, io::Empty> {
+        InterpreterBuilder {
+            code: String::new(),
+            output_handle: io::sink(),
+            input_handle: io::empty(),
+            program_counter_position: None,
+            program_counter_direction: None,
+            seed: None,
+            initial_stack: None,
+            options: Options::default(),
+        }
+    }
+}
+
+impl Default for InterpreterBuilder<io::Sink, io::Empty> {
+    fn default() -> InterpreterBuilder<io::Sink, io::Empty> {
+        InterpreterBuilder::new()
+    }
+}
+
+impl<Writable, Readable> InterpreterBuilder<Writable, Readable>
+where
+    Writable: Write,
+    Readable: BufRead,
+{
+    // Sets the program code to run
+    pub fn code(mut self, code: &str) -> InterpreterBuilder<Writable, Readable> {
+        self.code = code.to_string();
+        self
+    }
+
+    // Sets the handle that program output is written to
+    pub fn output<NewWritable>(
+        self,
+        output_handle: NewWritable,
+    ) -> InterpreterBuilder<NewWritable, Readable>
+    where
+        NewWritable: Write,
+    {
+        InterpreterBuilder {
+            code: self.code,
+            output_handle,
+            input_handle: self.input_handle,
+            program_counter_position: self.program_counter_position,
+            program_counter_direction: self.program_counter_direction,
+            seed: self.seed,
+            initial_stack: self.initial_stack,
+            options: self.options,
+        }
+    }
+
+    // Sets the handle that & and ~ read from
+    pub fn input<NewReadable>(
+        self,
+        input_handle: NewReadable,
+    ) -> InterpreterBuilder<Writable, NewReadable>
+    where
+        NewReadable: BufRead,
+    {
+        InterpreterBuilder {
+            code: self.code,
+            output_handle: self.output_handle,
+            input_handle,
+            program_counter_position: self.program_counter_position,
+            program_counter_direction: self.program_counter_direction,
+            seed: self.seed,
+            initial_stack: self.initial_stack,
+            options: self.options,
+        }
+    }
+
+    // Sets the initial position of the program counter
+    pub fn position(mut self, position: Coord) -> InterpreterBuilder<Writable, Readable> {
+        self.program_counter_position = Some(position);
+        self
+    }
+
+    // Sets the initial direction of the program counter
+    pub fn direction(mut self, direction: Direction) -> InterpreterBuilder<Writable, Readable> {
+        self.program_counter_direction = Some(direction);
+        self
+    }
+
+    // Sets the seed for the random number generator used by ?. This takes
+    // precedence over `seed` in the options, whichever is set first.
+    pub fn seed(mut self, seed: u64) -> InterpreterBuilder<Writable, Readable> {
+        self.seed = Some(seed);
+        self
+    }
+
+    // Preloads the stack with the passed values, which sit below anything the program
+    // pushes, so the last value is on top. This takes precedence over
+    // `initial_stack` in the options, whichever is set first.
+    pub fn stack(mut self, values: Vec<i64>) -> InterpreterBuilder<Writable, Readable> {
+        self.initial_stack = Some(values);
+        self
+    }
+
+    // Replaces all of the options that change how the interpreter behaves
+    pub fn options(mut self, options: Options) -> InterpreterBuilder<Writable, Readable> {
+        self.options = options;
+        self
+    }
+
+    // Creates the interpreter. May return any errors propagated from
+    // `Interpreter::with_options`.
+    pub fn build(mut self) -> Result<Interpreter<Writable, Readable>, BefungeError> {
+        if let Some(seed) = self.seed {
+            self.options.seed = Some(seed);
+        }
+        if let Some(initial_stack) = self.initial_stack {
+            self.options.initial_stack = initial_stack;
+        }
+
+        Interpreter::with_options(
+            &self.code,
+            self.output_handle,
+            self.input_handle,
+            self.program_counter_position,
+            self.program_counter_direction,
+            self.options,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let interpreter = InterpreterBuilder::new().code("5.@").build().unwrap();
+
+        assert_eq!(
+            interpreter.playfield().program_counter_position,
+            Coord { x: 0, y: 0 }
+        );
+        assert_eq!(
+            interpreter.playfield().program_counter_direction,
+            Direction::Right
+        );
+    }
+
+    #[test]
+    fn test_input() {
+        let mut output = Vec::new();
+        {
+            let mut interpreter = InterpreterBuilder::new()
+                .code("&2*.@")
+                .input("21\n".as_bytes())
+                .output(&mut output)
+                .build()
+                .unwrap();
+            interpreter.execute().unwrap();
+        }
+
+        assert_eq!(output, "42 ".as_bytes());
+    }
+
+    #[test]
+    fn test_options() {
+        let options = Options {
+            initial_stack: vec![7],
+            ..Options::default()
+        };
+
+        let mut interpreter = InterpreterBuilder::new()
+            .code("@")
+            .options(options)
+            .seed(1)
+            .build()
+            .unwrap();
+        assert_eq!(interpreter.execute().unwrap(), [7]);
+    }
+
+    #[test]
+    fn test_seed_before_options() {
+        let run = |seed_first: bool, seed: u64| {
+            let mut output = Vec::new();
+            {
+                let builder = InterpreterBuilder::new()
+                    .code(include_str!("../../tests/random_digits.bf"))
+                    .output(&mut output);
+                let builder = if seed_first {
+                    builder.seed(seed).options(Options::default())
+                } else {
+                    builder.options(Options::default()).seed(seed)
+                };
+
+                builder.build().unwrap().execute().unwrap();
+            }
+            output
+        };
+
+        for seed in 1..6 {
+            assert_eq!(run(true, seed), run(false, seed));
+        }
+    }
+
+    #[test]
+    fn test_stack_before_options() {
+        let mut interpreter = InterpreterBuilder::new()
+            .code("@")
+            .stack(vec![4, 2])
+            .options(Options::default())
+            .build()
+            .unwrap();
+        assert_eq!(interpreter.execute().unwrap(), [4, 2]);
+    }
+
+    #[test]
+    fn test_stack() {
+        let mut output = Vec::new();
+        {
+            let mut interpreter = InterpreterBuilder::new()
+                .code("+.@")
+                .output(&mut output)
+                .stack(vec![5, 6])
+                .build()
+                .unwrap();
+            interpreter.execute().unwrap();
+        }
+
+        assert_eq!(output, "11 ".as_bytes());
+    }
+
+    #[test]
+    fn test_stack_is_below_pushed_values() {
+        let mut interpreter = InterpreterBuilder::new()
+            .code("9@")
+            .stack(vec![1, 2])
+            .build()
+            .unwrap();
+        assert_eq!(interpreter.execute().unwrap(), [1, 2, 9]);
+    }
+
+    #[test]
+    fn test_out_of_bounds_position() {
+        let result = InterpreterBuilder::new()
+            .code("5.@")
+            .position(Coord { x: 3, y: 0 })
+            .build();
+
+        assert!(result.is_err());
+    }
+}
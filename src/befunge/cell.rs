@@ -0,0 +1,151 @@
+/* befunge/cell.rs - Contains the struct definition for playfield cell values
+ * Copyright 2018 Arnav Borborah
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::convert::TryFrom;
+
+use super::error::Error as BefungeError;
+use super::options::CellRange;
+
+// A value that can be stored in a playfield cell or written as a character.
+// Stack values are only valid cells between 0 and 255 inclusive, which is checked
+// once when converting with `Cell::try_from`, or within a narrower range with
+// `Cell::try_from_range`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell(pub u8);
+
+impl Cell {
+    // Converts a stack value to a cell, checking that it is within the passed range.
+    // Values between 128 and 255 return `BefungeError::NonAsciiValue` with
+    // `CellRange::Ascii`, and values outside of 0-255 always return
+    // `BefungeError::InvalidAsciiValue`.
+    pub fn try_from_range(value: i64, range: CellRange) -> Result<Cell, BefungeError> {
+        let cell = Cell::try_from(value)?;
+
+        if range == CellRange::Ascii && !cell.is_ascii() {
+            return Err(BefungeError::NonAsciiValue(value));
+        }
+        Ok(cell)
+    }
+
+    // Returns whether the value is in the ASCII range (between 0 and 127 inclusive)
+    pub fn is_ascii(self) -> bool {
+        self.0.is_ascii()
+    }
+}
+
+impl TryFrom<i64> for Cell {
+    type Error = BefungeError;
+
+    fn try_from(value: i64) -> Result<Cell, BefungeError> {
+        u8::try_from(value)
+            .map(Cell)
+            .map_err(|_| BefungeError::InvalidAsciiValue(value))
+    }
+}
+
+impl From<Cell> for char {
+    fn from(cell: Cell) -> char {
+        cell.0 as char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod try_from {
+        use super::*;
+
+        #[test]
+        fn test_in_range() {
+            assert_eq!(Cell::try_from(0).unwrap(), Cell(0));
+            assert_eq!(Cell::try_from(65).unwrap(), Cell(65));
+            assert_eq!(Cell::try_from(255).unwrap(), Cell(255));
+        }
+
+        #[test]
+        fn test_out_of_range() {
+            assert!(Cell::try_from(256).is_err());
+            assert!(Cell::try_from(-1).is_err());
+            assert!(Cell::try_from(i64::MAX).is_err());
+        }
+    }
+
+    mod try_from_range {
+        use super::*;
+
+        #[test]
+        fn test_byte_range() {
+            assert_eq!(
+                Cell::try_from_range(200, CellRange::Byte).unwrap(),
+                Cell(200)
+            );
+            assert!(Cell::try_from_range(256, CellRange::Byte).is_err());
+        }
+
+        #[test]
+        fn test_ascii_range() {
+            assert_eq!(
+                Cell::try_from_range(127, CellRange::Ascii).unwrap(),
+                Cell(127)
+            );
+
+            match Cell::try_from_range(128, CellRange::Ascii) {
+                Err(BefungeError::NonAsciiValue(value)) => assert_eq!(value, 128),
+                result => panic!("Expected a non-ASCII value, got {:?}", result),
+            }
+            match Cell::try_from_range(-1, CellRange::Ascii) {
+                Err(BefungeError::InvalidAsciiValue(value)) => assert_eq!(value, -1),
+                result => panic!("Expected an invalid value, got {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_ascii() {
+        assert!(Cell(127).is_ascii());
+        assert!(!Cell(128).is_ascii());
+    }
+
+    mod into_char {
+        use super::*;
+
+        fn convert(value: i64) -> char {
+            char::from(Cell::try_from(value).unwrap())
+        }
+
+        #[test]
+        fn test_basic() {
+            assert_eq!(convert(57), '9');
+            assert_eq!(convert(38), '&');
+            assert_eq!(convert(76), 'L');
+            assert_eq!(convert(97), 'a');
+            assert_eq!(convert(103), 'g');
+        }
+
+        #[test]
+        fn test_extended_character_set() {
+            assert_eq!(convert(233), 'é');
+            assert_eq!(convert(247), '÷');
+        }
+
+        #[test]
+        fn test_bounds() {
+            assert_eq!(convert(0), 0_u8 as char);
+            assert_eq!(convert(255), 'ÿ');
+        }
+    }
+}
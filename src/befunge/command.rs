@@ -0,0 +1,91 @@
+/* befunge/command.rs - Contains the classification of Befunge-93 commands
+ * Copyright 2018 Arnav Borborah
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// The kind of work a command does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommandCategory {
+    // + - * / % ! `
+    Arithmetic,
+    // 0-9 : \ $
+    Stack,
+    // > < ^ v ? # and space
+    Movement,
+    // _ | " @
+    Control,
+    // g p
+    Memory,
+    // . , & ~
+    IO,
+    // Anything that is not a Befunge-93 command
+    Other,
+}
+
+// Returns the category of the passed command. This is the single place commands
+// are classified, so every feature grouping commands by category agrees.
+pub fn command_category(command: char) -> CommandCategory {
+    match command {
+        '+' | '-' | '*' | '/' | '%' | '!' | '`' => CommandCategory::Arithmetic,
+        '0'..='9' | ':' | '\\' | '$' => CommandCategory::Stack,
+        '>' | '<' | '^' | 'v' | '?' | '#' | ' ' => CommandCategory::Movement,
+        '_' | '|' | '"' | '@' => CommandCategory::Control,
+        'g' | 'p' => CommandCategory::Memory,
+        '.' | ',' | '&' | '~' => CommandCategory::IO,
+        _ => CommandCategory::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io() {
+        assert_eq!(command_category('.'), CommandCategory::IO);
+        assert_eq!(command_category('~'), CommandCategory::IO);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(command_category('+'), CommandCategory::Arithmetic);
+        assert_eq!(command_category('`'), CommandCategory::Arithmetic);
+    }
+
+    #[test]
+    fn test_movement() {
+        assert_eq!(command_category('v'), CommandCategory::Movement);
+        assert_eq!(command_category('?'), CommandCategory::Movement);
+    }
+
+    #[test]
+    fn test_memory() {
+        assert_eq!(command_category('p'), CommandCategory::Memory);
+        assert_eq!(command_category('g'), CommandCategory::Memory);
+    }
+
+    #[test]
+    fn test_stack_and_control() {
+        assert_eq!(command_category('7'), CommandCategory::Stack);
+        assert_eq!(command_category('\\'), CommandCategory::Stack);
+        assert_eq!(command_category('_'), CommandCategory::Control);
+        assert_eq!(command_category('@'), CommandCategory::Control);
+    }
+
+    #[test]
+    fn test_other() {
+        assert_eq!(command_category('x'), CommandCategory::Other);
+        assert_eq!(command_category('A'), CommandCategory::Other);
+    }
+}
@@ -1,4 +1,4 @@
-/* befunge/error.rs - Contains the struct definition for Befunge-93 errors
+/* befunge/error.rs - Contains the enum definition for Befunge-93 errors
  * Copyright 2018 Arnav Borborah
  *
  * Licensed under the Apache License, Version 2.0 (the "License");
@@ -16,20 +16,158 @@
 
 use std::{error, fmt};
 
+use super::playfield::Coord;
+
 #[derive(Debug)]
-pub struct Error(pub String);
+pub enum Error {
+    // A general error described by its message, used when the host sets up or
+    // extends the interpreter incorrectly, such as with invalid options
+    Message(String),
+
+    // A `/` command popped 0 as the divisor
+    DivisionByZero {
+        dividend: i64,
+    },
+
+    // A `%` command popped 0 as the divisor
+    ModuloByZero {
+        dividend: i64,
+    },
+
+    // A cell outside of the playfield was accessed
+    OutOfBounds {
+        coord: Coord,
+    },
+
+    // The program counter reached a cell that is not a command
+    InvalidCommand {
+        command: char,
+        coord: Coord,
+    },
+
+    // Two playfields were compared, but their dimensions differ
+    DimensionMismatch {
+        expected: Coord,
+        actual: Coord,
+    },
+
+    // The program needs more input than was provided with `programmatic_input`
+    InputRequired,
+
+    // A value cannot be stored in a cell, since it is not between 0 and 255
+    InvalidAsciiValue(i64),
+
+    // A value is outside of `CellRange::Ascii`, since it is not between 0 and 127
+    NonAsciiValue(i64),
+
+    // Input read by `&` or `~` could not be parsed. `expected` describes what
+    // the input should have been, such as "integer".
+    InvalidInput {
+        input: String,
+        expected: &'static str,
+    },
+
+    // An `A` command popped two values that were not equal
+    AssertionFailed {
+        expected: i64,
+        actual: i64,
+    },
+
+    // A `k` command tried to iterate a command that cannot be iterated
+    NotIterable {
+        command: char,
+        coord: Coord,
+    },
+
+    // The program counter executed more consecutive spaces than
+    // `max_consecutive_spaces` allows, ending at `coord`
+    ConsecutiveSpacesExceeded {
+        limit: u64,
+        coord: Coord,
+    },
+
+    // The program counter executed more consecutive spaces than there are cells,
+    // so it is moving through a region of spaces it can never leave
+    StuckInEmptyRegion {
+        coord: Coord,
+    },
+
+    // The program did not halt within the allowed number of steps
+    StepLimitExceeded {
+        limit: u64,
+    },
+
+    // The program executed more put (`p`) commands than allowed
+    PutLimitExceeded {
+        limit: u64,
+    },
+}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            Error::Message(message) => write!(f, "{}", message),
+            Error::DivisionByZero { dividend } => write!(f, "Cannot divide {} by 0!", dividend),
+            Error::ModuloByZero { dividend } => write!(f, "Cannot mod {} by 0!", dividend),
+            Error::OutOfBounds { coord } => {
+                write!(f, "Location ({}, {}) is out of bounds!", coord.x, coord.y)
+            }
+            Error::InvalidCommand { command, coord } => write!(
+                f,
+                "{} at ({}, {}) is not a valid command!",
+                command, coord.x, coord.y
+            ),
+            Error::DimensionMismatch { expected, actual } => write!(
+                f,
+                "Cannot compare a {}x{} playfield with a {}x{} playfield!",
+                expected.x, expected.y, actual.x, actual.y
+            ),
+            Error::InputRequired => write!(f, "The program needs input, but none was provided!"),
+            Error::InvalidAsciiValue(value) => write!(
+                f,
+                "{} is not a valid ASCII value (between 0 and 255 inclusive)!",
+                value
+            ),
+            Error::NonAsciiValue(value) => write!(
+                f,
+                "{} is not an ASCII value (between 0 and 127 inclusive)!",
+                value
+            ),
+            Error::InvalidInput { input, expected } => {
+                write!(f, "{} is not a valid {}!", input, expected)
+            }
+            Error::AssertionFailed { expected, actual } => write!(
+                f,
+                "Assertion failed! Expected {}, but found {}.",
+                expected, actual
+            ),
+            Error::NotIterable { command, coord } => write!(
+                f,
+                "{} at ({}, {}) cannot be iterated by k!",
+                command, coord.x, coord.y
+            ),
+            Error::ConsecutiveSpacesExceeded { limit, coord } => write!(
+                f,
+                "More than {} consecutive spaces were executed, ending at ({}, {})! \
+                 The program counter may never reach @.",
+                limit, coord.x, coord.y
+            ),
+            Error::StuckInEmptyRegion { coord } => write!(
+                f,
+                "The program counter is stuck in a region of spaces at ({}, {})!",
+                coord.x, coord.y
+            ),
+            Error::StepLimitExceeded { limit } => {
+                write!(f, "The program did not halt within {} steps!", limit)
+            }
+            Error::PutLimitExceeded { limit } => {
+                write!(f, "More than {} put (p) commands were executed!", limit)
+            }
+        }
     }
 }
 
 impl error::Error for Error {
-    fn description(&self) -> &str {
-        &self.0
-    }
-
     fn cause(&self) -> Option<&dyn error::Error> {
         None
     }
@@ -14,21 +14,74 @@
  * limitations under the License.
  */
 
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+use std::collections::{HashMap, VecDeque};
 use std::error::Error as StdError;
-use std::io::{BufRead, Write};
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::time::{Duration, Instant};
 
+use super::cell::Cell;
+use super::command::{command_category, CommandCategory};
 // Throughout comments, befunge::Error will be referred to as BefungeError
 use super::error::Error as BefungeError;
+use super::options::{
+    CellRange, CharInputMode, ComparisonMode, CrlfMode, DivisionMode, Options, Padding,
+};
 use super::playfield::{Coord, Direction, Playfield};
+use super::stack::Stack;
 
 // Possible interpreter modes
-#[derive(Debug, PartialEq)]
-enum Mode {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    // Cells are pushed onto the stack as characters until the closing "
     String,
+    // Cells are executed as commands
     Command,
+    // The next cell is skipped, after a #
     Bridge,
+    // Cells are skipped until the closing ;, with the `funge98_comments` option
+    Comment,
+}
+
+// Wraps a closure supplied by the host so that the interpreter can still derive Debug
+struct Callback<F: ?Sized>(Box<F>);
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Callback")
+    }
+}
+
+// A handler for a custom command registered with `Interpreter::register_command`
+pub type CommandHandler = dyn FnMut(&mut Stack, &mut Playfield) -> Result<(), BefungeError>;
+
+// A function set with `Interpreter::set_on_stack_change`, which is passed the values
+// on the stack from bottom to top
+pub type StackObserver = dyn FnMut(&[i64]);
+
+// The outcome of executing a single step
+#[derive(Debug, PartialEq)]
+pub enum StepResult {
+    // The program can keep running
+    Continued,
+    // The program reached @ and has stopped
+    Halted,
+    // The next cell is & or ~, but not enough input has been provided with
+    // `Interpreter::provide_input` yet. Nothing was executed, so the host can wait
+    // for input however it likes, provide it, and step again.
+    NeedsInput,
+}
+
+// Resources used by a program, as returned by `Interpreter::execute_with_stats`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunStats {
+    pub steps: u64,
+    pub peak_stack_depth: usize,
+    pub bytes_written: u64,
+    pub elapsed: Duration,
 }
 
 // This struct handles the execution of the Befunge-93 code. An instance of this
@@ -40,10 +93,33 @@ where
     Readable: BufRead,
 {
     playfield: Playfield,
-    stack: Vec<i64>,
+    // The playfield as it was before execution started, restored by `reset`
+    initial_playfield: Playfield,
+    stack: Stack,
     output_handle: Writable,
     input_handle: Readable,
     mode: Mode,
+    rng: StdRng,
+    options: Options,
+    steps: u64,
+    last_command: Option<char>,
+    wrapped: bool,
+    bytes_written: u64,
+    lines_written: u64,
+    output_events: Vec<(u64, u8)>,
+    consecutive_spaces: u64,
+    put_writes: u64,
+    // Whether a carriage return is being held back to see if a line feed follows,
+    // only used with `CrlfMode::Coalesce`
+    pending_carriage_return: bool,
+    output_transform: Option<Callback<dyn FnMut(u8) -> Option<u8>>>,
+    custom_commands: HashMap<char, Callback<CommandHandler>>,
+    on_stack_change: Option<Callback<StackObserver>>,
+    // Input provided with `provide_input`, only used with `programmatic_input`
+    pending_input: Option<VecDeque<u8>>,
+    // The rest of the last line read by &, which may hold more integers for later
+    // & commands to read. Only kept while it has more than whitespace in it.
+    buffered_line: VecDeque<u8>,
 }
 
 impl<Writable, Readable> Interpreter<Writable, Readable>
@@ -60,84 +136,640 @@ where
         program_counter_position: Option<Coord>,
         program_counter_direction: Option<Direction>,
     ) -> Result<Interpreter<Writable, Readable>, BefungeError> {
+        Interpreter::with_options(
+            code,
+            output_handle,
+            input_handle,
+            program_counter_position,
+            program_counter_direction,
+            Options::default(),
+        )
+    }
+
+    // Same as `Interpreter::new`, but additionally takes options that change
+    // how the interpreter behaves
+    pub fn with_options(
+        code: &str,
+        output_handle: Writable,
+        input_handle: Readable,
+        program_counter_position: Option<Coord>,
+        program_counter_direction: Option<Direction>,
+        options: Options,
+    ) -> Result<Interpreter<Writable, Readable>, BefungeError> {
+        if let Some(alt_halt) = options.alt_halt {
+            if command_category(alt_halt) != CommandCategory::Other {
+                return Err(BefungeError::Message(format!(
+                    "{} is already a Befunge-93 command, so it cannot be the alternate halt!",
+                    alt_halt
+                )));
+            }
+        }
+
+        let code = match options.comment_char {
+            Some(comment_char) => strip_comment_lines(code, comment_char),
+            None => code.to_string(),
+        };
+
+        let (code, program_counter_position) = match options.entry_label {
+            Some(ref label) => {
+                let (code, position) = strip_labels(&code, label)?;
+                (code, Some(position))
+            }
+            None => (code, program_counter_position),
+        };
+
+        let pending_input = if options.programmatic_input {
+            Some(VecDeque::new())
+        } else {
+            None
+        };
+
+        let playfield = Playfield::with_padding(
+            &code,
+            program_counter_position.unwrap_or(Coord { x: 0, y: 0 }),
+            program_counter_direction.unwrap_or(Direction::Right),
+            match options.padding {
+                Padding::Space => ' ',
+                Padding::Null => '\0',
+            },
+        )?;
+
+        if let Some(ref label) = options.entry_label {
+            if !playfield.contains(&playfield.program_counter_position) {
+                return Err(BefungeError::Message(format!(
+                    "The label {} does not mark a cell of the playfield!",
+                    label
+                )));
+            }
+        }
+
         Ok(Interpreter {
-            playfield: Playfield::new(
-                code,
-                program_counter_position.unwrap_or(Coord { x: 0, y: 0 }),
-                program_counter_direction.unwrap_or(Direction::Right),
-            )?,
-            stack: Vec::new(),
+            initial_playfield: playfield.clone(),
+            playfield,
+            stack: initial_stack(&options),
             output_handle,
             input_handle,
             mode: Mode::Command,
+            rng: match options.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            options,
+            steps: 0,
+            last_command: None,
+            wrapped: false,
+            bytes_written: 0,
+            lines_written: 0,
+            output_events: Vec::new(),
+            consecutive_spaces: 0,
+            put_writes: 0,
+            pending_carriage_return: false,
+            output_transform: None,
+            custom_commands: HashMap::new(),
+            on_stack_change: None,
+            pending_input,
+            buffered_line: VecDeque::new(),
+        })
+    }
+
+    // Restores the interpreter to how it was before execution started, so the same
+    // program can be run again without parsing it again. Changes made with p are
+    // undone, the stack goes back to `initial_stack`, and every counter restarts
+    // from 0. With a seed, the random number generator restarts too, so ? makes the
+    // same choices again. Input already read from the input handle stays consumed,
+    // while input provided with `provide_input` is discarded.
+    pub fn reset(&mut self) {
+        self.playfield = self.initial_playfield.clone();
+        self.stack = initial_stack(&self.options);
+        self.mode = Mode::Command;
+        if let Some(seed) = self.options.seed {
+            self.rng = StdRng::seed_from_u64(seed);
+        }
+        self.steps = 0;
+        self.last_command = None;
+        self.wrapped = false;
+        self.bytes_written = 0;
+        self.lines_written = 0;
+        self.output_events.clear();
+        self.consecutive_spaces = 0;
+        self.put_writes = 0;
+        self.pending_carriage_return = false;
+        if let Some(ref mut pending) = self.pending_input {
+            pending.clear();
+        }
+        self.buffered_line.clear();
+    }
+
+    // Sets a function that every byte of program output passes through before it is
+    // written to the output handle. Returning `None` from the function drops the byte.
+    pub fn set_output_transform(&mut self, transform: Box<dyn FnMut(u8) -> Option<u8>>) {
+        self.output_transform = Some(Callback(transform));
+    }
+
+    // Sets a function that is called with the values on the stack, from bottom to
+    // top, after every step that changes the stack. Steps that leave the stack
+    // alone, or that return an error, do not call it.
+    pub fn set_on_stack_change(&mut self, callback: Box<StackObserver>) {
+        self.on_stack_change = Some(Callback(callback));
+    }
+
+    // Registers a handler that runs whenever the program executes `command`, which
+    // must not already be a Befunge-93 command. The handler may change the stack and
+    // the playfield, but it may not resize the playfield or move the program counter
+    // out of bounds; doing so makes the step return a BefungeError.
+    pub fn register_command(
+        &mut self,
+        command: char,
+        handler: Box<CommandHandler>,
+    ) -> Result<(), BefungeError> {
+        if command_category(command) != CommandCategory::Other {
+            return Err(BefungeError::Message(format!(
+                "{} is already a Befunge-93 command!",
+                command
+            )));
+        }
+
+        self.custom_commands.insert(command, Callback(handler));
+        Ok(())
+    }
+
+    // Moves the program counter to the passed position and sets its direction, so
+    // the next step executes the cell at that position. If the position is out of
+    // bounds, a BefungeError will be returned and the program counter is unchanged.
+    pub fn set_program_counter(
+        &mut self,
+        position: Coord,
+        direction: Direction,
+    ) -> Result<(), BefungeError> {
+        if !self.playfield.contains(&position) {
+            return Err(BefungeError::OutOfBounds { coord: position });
+        }
+
+        self.playfield.program_counter_position = position;
+        self.playfield.program_counter_direction = direction;
+        Ok(())
+    }
+
+    // Writes the values on the stack to the passed writer, one per line, starting
+    // from the bottom of the stack
+    pub fn save_stack(&self, mut writer: impl Write) -> io::Result<()> {
+        for value in self.stack.as_slice() {
+            writeln!(writer, "{}", value)?;
+        }
+        writer.flush()
+    }
+
+    // Replaces the stack with whitespace separated values read from the passed
+    // reader, such as those written by `save_stack`. The last value ends up on top.
+    // The loaded values are not part of `initial_stack`, so `self.reset` discards
+    // them; set `initial_stack` in the options instead to keep them across resets.
+    // May return the following errors:
+    //
+    // 1. If the values cannot be read, the respective io::Error will be returned.
+    //
+    // 2. If a value is not a valid integer, a BefungeError will be returned and the
+    //   stack is unchanged.
+    pub fn load_stack(&mut self, mut reader: impl Read) -> Result<(), Box<dyn StdError>> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+
+        let mut stack = Stack::with_capacity(self.options.stack_capacity);
+        for value in contents.split_whitespace() {
+            stack.push(
+                value
+                    .parse::<i64>()
+                    .map_err(|_| BefungeError::InvalidInput {
+                        input: value.to_string(),
+                        expected: "integer",
+                    })?,
+            );
+        }
+
+        self.stack = stack;
+        Ok(())
+    }
+
+    // Returns the playfield, which allows inspecting the grid and program counter
+    // after execution stops
+    pub fn playfield(&self) -> &Playfield {
+        &self.playfield
+    }
+
+    // Returns the values on the stack, from bottom to top, without copying them
+    pub fn stack(&self) -> &[i64] {
+        self.stack.as_slice()
+    }
+
+    // Returns the values on the stack, starting with the top of the stack. Anything
+    // that displays the stack should use this order, so the top of the stack
+    // always comes first.
+    pub fn stack_top_first(&self) -> Vec<i64> {
+        self.stack.as_slice().iter().rev().cloned().collect()
+    }
+
+    // Returns the mode the next step will execute in
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    // Returns the largest number of values the stack has held at once
+    pub fn peak_stack_depth(&self) -> usize {
+        self.stack.peak_depth()
+    }
+
+    // Returns the number of steps executed so far. Every cell the program counter
+    // visits counts as a step, including the @ that halts the program, and so does
+    // every iteration of k.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    // Returns the command executed by the last step, or `None` if the last step
+    // did not execute a command, e.g. because it was in string mode or skipped
+    // over by a bridge. Spaces count as commands.
+    pub fn last_command(&self) -> Option<char> {
+        self.last_command
+    }
+
+    // Returns whether the program counter wrapped around an edge of the playfield
+    // during the last step, either when moving at the end of it or when ' moved
+    // onto the fetched cell
+    pub fn wrapped(&self) -> bool {
+        self.wrapped
+    }
+
+    // Returns the number of bytes written to the output handle so far
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    // Returns every byte written so far along with the step that wrote it, in the
+    // order they were written. Output is only recorded when the
+    // `record_output_events` option is set.
+    pub fn output_events(&self) -> &[(u64, u8)] {
+        &self.output_events
+    }
+
+    // Returns, for every step that wrote output, the step along with all of the
+    // output written up to and including it. Like `self.output_events`, this needs
+    // the `record_output_events` option.
+    pub fn output_checkpoints(&self) -> Vec<(u64, Vec<u8>)> {
+        let mut checkpoints: Vec<(u64, Vec<u8>)> = Vec::new();
+        let mut output = Vec::new();
+
+        for &(step, byte) in &self.output_events {
+            output.push(byte);
+
+            match checkpoints.last_mut() {
+                Some(checkpoint) if checkpoint.0 == step => checkpoint.1.push(byte),
+                _ => checkpoints.push((step, output.clone())),
+            }
+        }
+
+        checkpoints
+    }
+
+    // Queues input for & and ~ to read when the `programmatic_input` option is set.
+    // Otherwise, input comes from the input handle and a BefungeError is returned.
+    pub fn provide_input(&mut self, input: &str) -> Result<(), BefungeError> {
+        match self.pending_input {
+            Some(ref mut pending) => {
+                pending.extend(input.bytes());
+                Ok(())
+            }
+            None => Err(BefungeError::Message(
+                "Input can only be provided when the programmatic_input option is set!".to_string(),
+            )),
+        }
+    }
+
+    // Executes the Befunge-93 code until it halts, and returns a copy of the values
+    // left on the stack, from bottom to top, so the top of the stack is last.
+    // May return any errors propagated from `self.step`. If the program needs more
+    // input than was provided with `self.provide_input`, `BefungeError::InputRequired`
+    // will be returned.
+    pub fn execute(&mut self) -> Result<Vec<i64>, Box<dyn StdError>> {
+        loop {
+            match self.step()? {
+                StepResult::Continued => (),
+                StepResult::Halted => return Ok(self.stack.as_slice().to_vec()),
+                StepResult::NeedsInput => return Err(BefungeError::InputRequired.into()),
+            }
+        }
+    }
+
+    // Same as `self.execute`, but returns the resources used once the program halts.
+    // The counters include any steps executed before this call; only the elapsed
+    // time is limited to this call.
+    pub fn execute_with_stats(&mut self) -> Result<RunStats, Box<dyn StdError>> {
+        let start = Instant::now();
+        self.execute()?;
+
+        Ok(RunStats {
+            steps: self.steps,
+            peak_stack_depth: self.stack.peak_depth(),
+            bytes_written: self.bytes_written,
+            elapsed: start.elapsed(),
         })
     }
 
-    // Executes the Befunge-93 code. May return the following errors:
+    // Runs the program for at most `max_steps` steps and returns whether it reached @.
+    // A program that errors does not halt. Afterwards the interpreter is left either
+    // halted, stopped at the cell that errored, or ready to continue running from
+    // where the step budget ran out.
+    pub fn halts_within(&mut self, max_steps: u64) -> bool {
+        for _ in 0..max_steps {
+            match self.step() {
+                Ok(StepResult::Continued) => (),
+                Ok(StepResult::Halted) => return true,
+                Ok(StepResult::NeedsInput) | Err(_) => return false,
+            }
+        }
+        false
+    }
+
+    // Runs at most `max_steps` steps, so a host can interleave the program with other
+    // work. Returns `StepResult::Halted` or `StepResult::NeedsInput` as soon as a step
+    // does, or `StepResult::Continued` if the program can keep running in another
+    // slice. May return any errors propagated from `self.step`.
+    pub fn run_slice(&mut self, max_steps: u64) -> Result<StepResult, Box<dyn StdError>> {
+        for _ in 0..max_steps {
+            match self.step()? {
+                StepResult::Continued => (),
+                result => return Ok(result),
+            }
+        }
+        Ok(StepResult::Continued)
+    }
+
+    // Executes the cell under the program counter and then moves the program counter.
+    // Once the program has halted, further calls keep returning `StepResult::Halted`.
+    //
+    // By default, & and ~ read from the input handle, blocking until input arrives,
+    // which does not use any CPU while waiting. With `programmatic_input`, they
+    // return `StepResult::NeedsInput` instead when not enough input has been
+    // provided, so a host can wait on its own input source without spinning.
+    //
+    // May return the following errors:
     //
     // 1. Any errors propagated from `self.run_unary_operation`, `self.run_binary_operation`,
     //   or `self.run_other_operation`.
     //
     // 2. If an unexpected command is met while parsing in command mode, a BefungeError
     //   will be returned.
-    pub fn execute(&mut self) -> Result<(), Box<dyn StdError>> {
-        loop {
-            // Empty program is an infinite loop
-            if self.playfield.dimensions.x == 0 {
-                continue;
+    //
+    // 3. If `max_consecutive_spaces` is set and more spaces than that are executed
+    //   in a row, `BefungeError::ConsecutiveSpacesExceeded` will be returned.
+    //
+    // 4. If more spaces than there are cells in the playfield are executed in a row,
+    //   the program counter can never leave them, so `BefungeError::StuckInEmptyRegion`
+    //   will be returned.
+    //
+    // 5. If `max_steps` is set and that many steps have been executed,
+    //   `BefungeError::StepLimitExceeded` will be returned without executing anything.
+    //
+    // 6. If the output handle cannot be flushed, the respective io::Error will be
+    //   returned.
+    pub fn step(&mut self) -> Result<StepResult, Box<dyn StdError>> {
+        let result = self.execute_step();
+
+        // Unless `autoflush` is set, output is only flushed once the program stops
+        // running, so nothing is left unwritten when a step halts, waits or fails
+        match result {
+            Ok(StepResult::Continued) => (),
+            // The error from the step says more than a failed flush would
+            Err(_) => {
+                let _ = self.output_handle.flush();
+            }
+            Ok(_) => self.output_handle.flush()?,
+        }
+        result
+    }
+
+    // Flushes any output that has not been written to the output handle yet. This is
+    // only needed with `run_slice` or `step`, when a host wants output to show up
+    // before the program stops and `autoflush` is not set.
+    pub fn flush_output(&mut self) -> io::Result<()> {
+        self.output_handle.flush()
+    }
+
+    // Executes a single step for `self.step`, without flushing the output
+    fn execute_step(&mut self) -> Result<StepResult, Box<dyn StdError>> {
+        // An empty program has nothing to execute, so it halts immediately
+        if self.playfield.dimensions.x == 0 {
+            return Ok(StepResult::Halted);
+        }
+
+        if self.output_limit_reached() {
+            return Ok(StepResult::Halted);
+        }
+
+        self.check_step_limit()?;
+
+        let curr_char = self.playfield.get_next_character();
+        if self.mode == Mode::Command && !self.input_ready(curr_char) {
+            return Ok(StepResult::NeedsInput);
+        }
+
+        self.steps += 1;
+        self.wrapped = false;
+
+        // Null padding cells are executed like spaces, so they count as spaces too
+        let is_space =
+            curr_char == ' ' || (curr_char == '\0' && self.options.padding == Padding::Null);
+
+        if self.mode == Mode::Command && is_space {
+            self.consecutive_spaces += 1;
+
+            if let Some(limit) = self.options.max_consecutive_spaces {
+                if self.consecutive_spaces > limit {
+                    return Err(BefungeError::ConsecutiveSpacesExceeded {
+                        limit,
+                        coord: self.playfield.program_counter_position,
+                    }
+                    .into());
+                }
+            }
+
+            // Spaces leave the stack and direction unchanged, so once the program
+            // counter has run over more spaces than there are cells, it has revisited
+            // a cell in the same state and will loop forever
+            let cells = (self.playfield.dimensions.x * self.playfield.dimensions.y) as u64;
+            if self.consecutive_spaces > cells {
+                return Err(BefungeError::StuckInEmptyRegion {
+                    coord: self.playfield.program_counter_position,
+                }
+                .into());
             }
+        } else {
+            self.consecutive_spaces = 0;
+        }
+
+        self.last_command = match self.mode {
+            Mode::Command => Some(curr_char),
+            _ => None,
+        };
 
-            let curr_char = self.playfield.get_next_character();
+        let stack_changes = self.stack.changes();
 
-            match self.mode {
-                Mode::Bridge => self.mode = Mode::Command,
+        let halted = match self.mode {
+            // Per the spec, # skips the next cell whatever it holds, so in `#@` the
+            // @ is skipped and the program keeps running
+            Mode::Bridge => {
+                self.mode = Mode::Command;
+                None
+            }
 
-                Mode::String => match curr_char {
+            Mode::String => {
+                match curr_char {
                     '"' => self.mode = Mode::Command,
                     _ => self.stack.push(curr_char as i64),
-                },
+                }
+                None
+            }
 
-                Mode::Command => match curr_char {
-                    '0'..='9' => self.stack.push(curr_char.to_digit(10).unwrap() as i64),
+            Mode::Comment => {
+                if curr_char == ';' {
+                    self.mode = Mode::Command;
+                }
+                None
+            }
 
-                    '!' | '_' | '|' | ':' | '$' | '.' | ',' => {
-                        self.run_unary_operation(curr_char)?
-                    }
+            Mode::Command => self.run_command(curr_char)?,
+        };
 
-                    '+' | '-' | '*' | '/' | '%' | '`' | '\\' | 'g' => {
-                        self.run_binary_operation(curr_char)?
-                    }
+        // A halt inside k may follow changes made by earlier iterations
+        if let Some(Callback(ref mut callback)) = self.on_stack_change {
+            if self.stack.changes() != stack_changes {
+                callback(self.stack.as_slice());
+            }
+        }
 
-                    ' ' | '>' | '<' | '^' | 'v' | '?' | '"' | '#' | 'p' | '&' | '~' => {
-                        self.run_other_operation(curr_char)?
-                    }
+        if let Some(result) = halted {
+            return Ok(result);
+        }
 
-                    '@' => break,
+        if self.output_limit_reached() {
+            return Ok(StepResult::Halted);
+        }
 
-                    _ => {
-                        return Err(
-                            BefungeError(format!("{} is not a valid command!", curr_char)).into(),
-                        );
-                    }
-                },
+        // A command may already have wrapped while moving within the step
+        self.wrapped |= self.playfield.update_program_counter();
+        Ok(StepResult::Continued)
+    }
+
+    // Executes a single command, for both a step in command mode and every iteration
+    // of k. Returns the result the step should end with if the command halts the
+    // program. May return the following errors:
+    //
+    // 1. Any errors propagated up from the operation being executed.
+    //
+    // 2. If the command is not a valid command, a BefungeError will be returned.
+    fn run_command(&mut self, command: char) -> Result<Option<StepResult>, Box<dyn StdError>> {
+        match command {
+            _ if self.options.alt_halt == Some(command) => return self.halt().map(Some),
+
+            '0'..='9' => self.stack.push(command.to_digit(10).unwrap() as i64),
+
+            '!' | '_' | '|' | ':' | '$' | '.' | ',' => self.run_unary_operation(command)?,
+
+            '+' | '-' | '*' | '/' | '%' | '`' | '\\' | 'g' => self.run_binary_operation(command)?,
+
+            ' ' | '>' | '<' | '^' | 'v' | '?' | '"' | '#' | 'p' | '&' | '~' => {
+                self.run_other_operation(command)?
+            }
+
+            ';' if self.options.funge98_comments => self.run_other_operation(command)?,
+
+            '\'' if self.options.funge98_fetch => self.run_other_operation(command)?,
+
+            'k' if self.options.funge98_iterate => return self.run_iterate_operation(),
+
+            '@' => return self.halt().map(Some),
+
+            '\0' if self.options.padding == Padding::Null => (),
+
+            'A' if self.options.extended_commands => {
+                let expected = self.stack.pop();
+                let actual = self.stack.pop();
+
+                if expected != actual {
+                    return Err(BefungeError::AssertionFailed { expected, actual }.into());
+                }
+            }
+
+            _ if self.custom_commands.contains_key(&command) => self.run_custom_command(command)?,
+
+            _ => {
+                return Err(BefungeError::InvalidCommand {
+                    command,
+                    coord: self.playfield.program_counter_position,
+                }
+                .into());
             }
+        }
+        Ok(None)
+    }
 
-            self.playfield.update_program_counter();
+    // Returns a BefungeError if `max_steps` is set and that many steps have been executed
+    fn check_step_limit(&self) -> Result<(), BefungeError> {
+        if self.options.max_steps == Some(self.steps) {
+            return Err(BefungeError::StepLimitExceeded { limit: self.steps });
         }
         Ok(())
     }
 
+    // Returns whether the program has written as many lines as `max_output_lines` allows
+    fn output_limit_reached(&self) -> bool {
+        self.options.max_output_lines == Some(self.lines_written)
+    }
+
+    // Runs the handler registered for the passed command, and checks that it left
+    // the playfield in a valid state. If it did not, the program counter and the
+    // dimensions of the playfield are restored before the BefungeError is returned,
+    // so the interpreter can still be stepped or inspected afterwards.
+    fn run_custom_command(&mut self, command: char) -> Result<(), Box<dyn StdError>> {
+        let dimensions = self.playfield.dimensions;
+        let position = self.playfield.program_counter_position;
+
+        let handler = self.custom_commands.get_mut(&command).unwrap();
+        (handler.0)(&mut self.stack, &mut self.playfield)?;
+
+        let new_position = self.playfield.program_counter_position;
+        let err = if self.playfield.dimensions != dimensions {
+            BefungeError::Message(format!(
+                "The handler for {} changed the dimensions of the playfield!",
+                command
+            ))
+        } else if !self.playfield.contains(&new_position) {
+            BefungeError::Message(format!(
+                "The handler for {} moved the program counter out of bounds to ({}, {})!",
+                command, new_position.x, new_position.y
+            ))
+        } else {
+            return Ok(());
+        };
+
+        // The handler may have changed `dimensions` directly or resized the grid, so
+        // resize the grid back to match the original dimensions either way
+        let fill = match self.options.padding {
+            Padding::Space => ' ',
+            Padding::Null => '\0',
+        };
+        self.playfield.program_counter_position = position;
+        self.playfield.resize(dimensions.x, dimensions.y, fill)?;
+
+        Err(err.into())
+    }
+
     // Executes unary operations. May return the following errors:
     //
     // 1. If a conversion from a integer to a character is not possible, a BefungeError
     //   will be returned.
     //
-    // 2. TODO: Writing to output handle
-    //
-    // 3. If the output handle cannot be flushed, the respective io::Error will be
-    //   returned.
+    // 2. Any errors propagated up from `self.write_output`.
     fn run_unary_operation(&mut self, operation: char) -> Result<(), Box<dyn StdError>> {
-        let value = self.stack.pop().unwrap_or(0);
+        let value = self.stack.pop();
 
         match operation {
             '!' => self.stack.push((value == 0) as i64),
@@ -158,14 +790,111 @@ where
                 self.stack.push(value);
             }
             '$' => (),
-            '.' => {
-                write!(self.output_handle, "{} ", value)?;
-                self.output_handle.flush()?;
+            '.' if self.options.no_trailing_space => {
+                self.write_output(value.to_string().as_bytes())?
             }
+            '.' => self.write_output(format!("{} ", value).as_bytes())?,
             _ => {
-                write!(self.output_handle, "{}", convert_int_to_char(value)?)?;
-                self.output_handle.flush()?;
+                // strict_ascii limits , alone to the same range as `CellRange::Ascii`
+                let range = if self.options.strict_ascii {
+                    CellRange::Ascii
+                } else {
+                    self.options.cell_range
+                };
+
+                let mut buffer = [0; 4];
+                let character =
+                    char::from(Cell::try_from_range(value, range)?).encode_utf8(&mut buffer);
+
+                self.write_output(character.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    // Halts the program, first writing a carriage return that was held back to see
+    // if a line feed follows
+    fn halt(&mut self) -> Result<StepResult, Box<dyn StdError>> {
+        if self.pending_carriage_return {
+            self.pending_carriage_return = false;
+            self.write_transformed_output(b"\r")?;
+        }
+
+        Ok(StepResult::Halted)
+    }
+
+    // Writes program output to the output handle, coalescing "\r\n" into "\n" with
+    // `CrlfMode::Coalesce`. May return the same errors as `write_transformed_output`.
+    fn write_output(&mut self, bytes: &[u8]) -> Result<(), Box<dyn StdError>> {
+        if self.options.crlf == CrlfMode::PassThrough {
+            return self.write_transformed_output(bytes);
+        }
+
+        let mut coalesced = Vec::with_capacity(bytes.len() + 1);
+        for &byte in bytes {
+            if self.pending_carriage_return {
+                self.pending_carriage_return = false;
+                if byte != b'\n' {
+                    coalesced.push(b'\r');
+                }
+            }
+
+            if byte == b'\r' {
+                self.pending_carriage_return = true;
+            } else {
+                coalesced.push(byte);
+            }
+        }
+
+        self.write_transformed_output(&coalesced)
+    }
+
+    // Writes bytes to the output handle, passing each byte through the output
+    // transform first if one is set. May return the following errors:
+    //
+    // 1. If the output cannot be written, the respective io::Error will be returned.
+    //
+    // 2. With `autoflush`, if the output handle cannot be flushed, the respective
+    //   io::Error will be returned.
+    fn write_transformed_output(&mut self, bytes: &[u8]) -> Result<(), Box<dyn StdError>> {
+        let transformed;
+        let bytes = match self.output_transform {
+            Some(Callback(ref mut transform)) => {
+                transformed = bytes
+                    .iter()
+                    .filter_map(|&byte| transform(byte))
+                    .collect::<Vec<_>>();
+                &transformed[..]
+            }
+            None => bytes,
+        };
+
+        // Only write up to the last line allowed by `max_output_lines`
+        let bytes = match self.options.max_output_lines {
+            Some(limit) => {
+                let mut end = 0;
+                while end < bytes.len() && self.lines_written < limit {
+                    if bytes[end] == b'\n' {
+                        self.lines_written += 1;
+                    }
+                    end += 1;
+                }
+                &bytes[..end]
             }
+            None => bytes,
+        };
+
+        self.output_handle.write_all(bytes)?;
+        self.bytes_written += bytes.len() as u64;
+
+        if self.options.record_output_events {
+            let step = self.steps;
+            self.output_events
+                .extend(bytes.iter().map(|&byte| (step, byte)));
+        }
+
+        if self.options.autoflush {
+            self.output_handle.flush()?;
         }
         Ok(())
     }
@@ -180,34 +909,72 @@ where
     //
     // 3. Any errors propagated up from `self.playfield.get_character_at`.
     fn run_binary_operation(&mut self, operation: char) -> Result<(), Box<dyn StdError>> {
-        let (a, b) = (self.stack.pop().unwrap_or(0), self.stack.pop().unwrap_or(0));
+        if operation == 'g' {
+            let position = self.pop_coord();
+            let value = if self.options.integer_cells {
+                self.playfield.get_value_at(&position)?
+            } else {
+                self.playfield.get_character_at(&position)? as i64
+            };
+
+            self.stack.push(value);
+            return Ok(());
+        }
+
+        let (a, b) = (self.stack.pop(), self.stack.pop());
 
         match operation {
             '+' => self.stack.push(b + a),
             '-' => self.stack.push(b - a),
             '*' => self.stack.push(b * a),
             '/' => match a {
-                0 => return Err(BefungeError(format!("Cannot divide {} by 0!", b)).into()),
-                _ => self.stack.push(b / a),
+                0 => return Err(BefungeError::DivisionByZero { dividend: b }.into()),
+                // i64::MIN / -1 overflows, so it wraps back to i64::MIN
+                _ => self.stack.push(match self.options.division {
+                    DivisionMode::Truncated => b.wrapping_div(a),
+                    DivisionMode::Floored => floored_division(b, a),
+                }),
             },
             '%' => match a {
-                0 => return Err(BefungeError(format!("Cannot mod {} by 0!", b)).into()),
-                _ => self.stack.push(b % a),
+                0 => return Err(BefungeError::ModuloByZero { dividend: b }.into()),
+                _ => self.stack.push(match self.options.division {
+                    DivisionMode::Truncated => b.wrapping_rem(a),
+                    DivisionMode::Floored => floored_modulo(b, a),
+                }),
+            },
+            '`' => match self.options.comparison {
+                ComparisonMode::Signed => self.stack.push((b > a) as i64),
+                ComparisonMode::Byte => self.stack.push(((b & 0xff) > (a & 0xff)) as i64),
             },
-            '`' => self.stack.push((b > a) as i64),
 
             '\\' => {
                 self.stack.push(a);
                 self.stack.push(b);
             }
 
-            _ => self
-                .stack
-                .push(self.playfield.get_character_at(&Coord { y: a, x: b })? as i64),
+            _ => unreachable!(),
         }
         Ok(())
     }
 
+    // Pops the position used by g and p. Per the spec y is popped first and then x,
+    // unless `swap_pg_coords` is set.
+    fn pop_coord(&mut self) -> Coord {
+        let (first, second) = (self.stack.pop(), self.stack.pop());
+
+        if self.options.swap_pg_coords {
+            Coord {
+                x: first,
+                y: second,
+            }
+        } else {
+            Coord {
+                x: second,
+                y: first,
+            }
+        }
+    }
+
     // Executes other operations (except digits and @). May return the following errors:
     //
     // 1. Any errors propagated up from `self.playfield.set_character_at`.
@@ -218,8 +985,7 @@ where
     // 3. For the & command, if a non-integer value is entered, a BefungeError will
     //   be returned.
     //
-    // 4. For the ~ command, if a non-char value is entered, a BefungeError will
-    //   be returned.
+    // 4. For the ~ command, any errors propagated up from `self.read_character`.
     fn run_other_operation(&mut self, operation: char) -> Result<(), Box<dyn StdError>> {
         match operation {
             ' ' => (),
@@ -228,7 +994,7 @@ where
             '^' => self.playfield.program_counter_direction = Direction::Up,
             'v' => self.playfield.program_counter_direction = Direction::Down,
             '?' => {
-                self.playfield.program_counter_direction = match thread_rng().gen_range(0, 4) {
+                self.playfield.program_counter_direction = match self.rng.gen_range(0..4) {
                     0 => Direction::Up,
                     1 => Direction::Down,
                     2 => Direction::Left,
@@ -236,62 +1002,462 @@ where
                 }
             }
             '"' => self.mode = Mode::String,
+            ';' => self.mode = Mode::Comment,
+            '\'' => {
+                // Moving onto the fetched cell here means the usual move after the
+                // step skips over it
+                self.wrapped |= self.playfield.update_program_counter();
+
+                let position = self.playfield.program_counter_position;
+                let value = if self.options.integer_cells {
+                    self.playfield.get_value_at(&position)?
+                } else {
+                    self.playfield.get_next_character() as i64
+                };
+                self.stack.push(value);
+            }
             '#' => self.mode = Mode::Bridge,
             'p' => {
-                let position = Coord {
-                    y: self.stack.pop().unwrap_or(0),
-                    x: self.stack.pop().unwrap_or(0),
-                };
-                let popped_value = self.stack.pop().unwrap_or(0);
+                self.put_writes += 1;
+                if let Some(limit) = self.options.max_put_writes {
+                    if self.put_writes > limit {
+                        return Err(BefungeError::PutLimitExceeded { limit }.into());
+                    }
+                }
 
-                self.playfield
-                    .set_character_at(&position, convert_int_to_char(popped_value)?)?;
+                let position = self.pop_coord();
+                let popped_value = self.stack.pop();
+
+                if self.options.integer_cells {
+                    self.playfield.set_value_at(&position, popped_value)?;
+                } else {
+                    let cell = Cell::try_from_range(popped_value, self.options.cell_range)?;
+                    self.playfield
+                        .set_character_at(&position, char::from(cell))?;
+                }
             }
             '&' => {
-                let mut input = String::new();
-                self.input_handle.read_line(&mut input)?;
+                // At the end of the input there is no integer to read, so -1 is pushed
+                let token = match self.read_integer_token()? {
+                    Some(token) => token,
+                    None => {
+                        self.stack.push(-1);
+                        return Ok(());
+                    }
+                };
+
+                let digits = token
+                    .chars()
+                    .filter(|character| !self.options.int_separators.contains(character))
+                    .collect::<String>();
 
                 self.stack.push(
-                    input
-                        .trim()
+                    digits
                         .parse::<i64>()
-                        .map_err(|_| BefungeError(format!("{} is not a valid integer!", input)))?,
+                        .map_err(|_| BefungeError::InvalidInput {
+                            input: token,
+                            expected: "integer",
+                        })?,
                 );
             }
             _ => {
-                let mut input = String::new();
-                self.input_handle.read_line(&mut input)?;
-
-                self.stack.push(
-                    input
-                        .trim()
-                        .parse::<char>()
-                        .map_err(|_| BefungeError(format!("{} is not a valid character!", input)))?
-                        as i64,
-                );
+                let value = self.read_character()?;
+                self.stack.push(value);
             }
         }
         Ok(())
     }
+
+    // Executes the k command, which runs the next cell in the current direction as
+    // many times as the popped count. Every iteration counts as a step of its own.
+    // Returns the result the step should end with if the iterated command halts the
+    // program, reaches the output limit, or needs input that has not been provided. May return the following errors:
+    //
+    // 1. Any errors propagated up from `self.run_command`.
+    //
+    // 2. If the next cell is another k, `BefungeError::NotIterable` will be returned.
+    //
+    // 3. If `max_steps` is set and the iterations reach it,
+    //   `BefungeError::StepLimitExceeded` will be returned.
+    fn run_iterate_operation(&mut self) -> Result<Option<StepResult>, Box<dyn StdError>> {
+        let count = self.stack.pop();
+
+        // Like ', moving onto the iterated cell here means the usual move after the
+        // step skips over it, including when the count is 0
+        let (position, wrapped) = (self.playfield.program_counter_position, self.wrapped);
+        self.wrapped |= self.playfield.update_program_counter();
+        let command = self.playfield.get_next_character();
+
+        if count > 0 && command == 'k' {
+            return Err(BefungeError::NotIterable {
+                command,
+                coord: self.playfield.program_counter_position,
+            }
+            .into());
+        }
+
+        for iteration in 0..count {
+            // `input_ready` only covers the first iteration, so when later ones run
+            // out of provided input, the rest of the count is left for k to resume
+            if !self.input_ready(command) {
+                self.stack.push(count - iteration);
+                self.playfield.program_counter_position = position;
+                self.wrapped = wrapped;
+                return Ok(Some(StepResult::NeedsInput));
+            }
+
+            self.check_step_limit()?;
+            self.steps += 1;
+
+            if let Some(result) = self.run_command(command)? {
+                return Ok(Some(result));
+            }
+
+            if self.output_limit_reached() {
+                return Ok(Some(StepResult::Halted));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Returns whether there is enough provided input to execute the passed command.
+    // This is always true when reading from the input handle, which blocks instead.
+    fn input_ready(&self, command: char) -> bool {
+        let pending = match self.pending_input {
+            Some(ref pending) => pending,
+            None => return true,
+        };
+
+        // k reads input through the command it iterates, so it waits for that input
+        // before popping its count. Iterating k itself is an error, not a wait.
+        if command == 'k'
+            && self.options.funge98_iterate
+            && self.stack.last().is_some_and(|&count| count > 0)
+        {
+            let iterated = self.playfield.peek_next_character();
+            return iterated == 'k' || self.input_ready(iterated);
+        }
+
+        if (command == '&' || command == '~') && !self.buffered_line.is_empty() {
+            return true;
+        }
+
+        match command {
+            '~' if self.options.char_input == CharInputMode::Byte => !pending.is_empty(),
+            '&' | '~' => pending.contains(&b'\n'),
+            _ => true,
+        }
+    }
+
+    // Reads a line of input, including the newline, from the provided input with
+    // `programmatic_input` or from the input handle otherwise. Returns the number of
+    // bytes read, which is 0 only at the end of the input.
+    fn read_input_line(&mut self, line: &mut String) -> Result<usize, Box<dyn StdError>> {
+        let mut bytes = Vec::new();
+        let length = self.read_input_line_bytes(&mut bytes)?;
+
+        match String::from_utf8(bytes) {
+            Ok(text) => line.push_str(&text),
+            Err(err) => {
+                return Err(BefungeError::InvalidInput {
+                    input: String::from_utf8_lossy(err.as_bytes().trim_ascii()).into_owned(),
+                    expected: "UTF-8 line",
+                }
+                .into());
+            }
+        }
+        Ok(length)
+    }
+
+    // Reads a line of input like `read_input_line`, without requiring it to be UTF-8
+    fn read_input_line_bytes(&mut self, line: &mut Vec<u8>) -> Result<usize, Box<dyn StdError>> {
+        match self.pending_input {
+            Some(ref mut pending) => {
+                let end = pending
+                    .iter()
+                    .position(|&byte| byte == b'\n')
+                    .map_or(pending.len(), |newline| newline + 1);
+
+                line.extend(pending.drain(..end));
+                Ok(end)
+            }
+            None => {
+                // Show any prompt the program wrote before waiting for input
+                self.output_handle.flush()?;
+                Ok(self.input_handle.read_until(b'\n', line)?)
+            }
+        }
+    }
+
+    // Reads the next whitespace-separated token for the & command, reading a new
+    // line only once the buffered line has no tokens left. Whitespace listed in
+    // `int_separators` does not separate tokens. Returns `None` at the end of the
+    // input.
+    fn read_integer_token(&mut self) -> Result<Option<String>, Box<dyn StdError>> {
+        while self.buffered_line.is_empty() {
+            let mut line = String::new();
+            if self.read_input_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+
+            self.buffered_line.extend(line.bytes());
+            self.discard_blank_buffered_line();
+        }
+
+        let separators = &self.options.int_separators;
+        let is_delimiter =
+            |byte: u8| byte.is_ascii_whitespace() && !separators.contains(&(byte as char));
+
+        while self
+            .buffered_line
+            .front()
+            .is_some_and(|&byte| is_delimiter(byte))
+        {
+            self.buffered_line.pop_front();
+        }
+
+        let end = self
+            .buffered_line
+            .iter()
+            .position(|&byte| is_delimiter(byte))
+            .unwrap_or(self.buffered_line.len());
+        let token = String::from_utf8(self.buffered_line.drain(..end).collect())?;
+
+        self.discard_blank_buffered_line();
+        Ok(Some(token))
+    }
+
+    // Drops the buffered line once nothing but whitespace is left in it, so the
+    // line break after the last integer on a line is not read by a later ~
+    fn discard_blank_buffered_line(&mut self) {
+        if self
+            .buffered_line
+            .iter()
+            .all(|byte| byte.is_ascii_whitespace())
+        {
+            self.buffered_line.clear();
+        }
+    }
+
+    // Reads a single byte of input from the provided input with `programmatic_input`
+    // or from the input handle otherwise. Returns `None` if there is no input left.
+    fn read_input_byte(&mut self) -> Result<Option<u8>, Box<dyn StdError>> {
+        if let Some(ref mut pending) = self.pending_input {
+            return Ok(pending.pop_front());
+        }
+
+        self.output_handle.flush()?;
+        let byte = self.input_handle.fill_buf()?.first().cloned();
+        if byte.is_some() {
+            self.input_handle.consume(1);
+        }
+        Ok(byte)
+    }
+
+    // Reads a character for the ~ command according to `self.options.char_input`.
+    // Characters left on a line after the integers read by & are read one at a time,
+    // skipping whitespace unless in byte mode. At the end of the input, -1 is returned.
+    // May return the following errors:
+    //
+    // 1. If the input cannot be read, the respective io::Error will be returned.
+    //
+    // 2. If the line read is not a single character (or not a single ASCII
+    //   character in ASCII mode, or not a single byte in single byte mode), a
+    //   BefungeError will be returned.
+    fn read_character(&mut self) -> Result<i64, Box<dyn StdError>> {
+        if self.options.char_input == CharInputMode::Byte {
+            if let Some(byte) = self.buffered_line.pop_front() {
+                self.discard_blank_buffered_line();
+                return Ok(byte as i64);
+            }
+            return Ok(self.read_input_byte()?.map_or(-1, |byte| byte as i64));
+        }
+
+        if self.options.char_input == CharInputMode::SingleByte {
+            return self.read_single_byte();
+        }
+
+        let character = if self.buffered_line.is_empty() {
+            let mut input = String::new();
+            if self.read_input_line(&mut input)? == 0 {
+                return Ok(-1);
+            }
+
+            input
+                .trim()
+                .parse::<char>()
+                .map_err(|_| BefungeError::InvalidInput {
+                    input: input.trim().to_string(),
+                    expected: "character",
+                })?
+        } else {
+            self.read_buffered_character()?
+        };
+
+        if self.options.char_input == CharInputMode::Ascii && !character.is_ascii() {
+            return Err(BefungeError::InvalidInput {
+                input: character.to_string(),
+                expected: "ASCII character",
+            }
+            .into());
+        }
+
+        Ok(character as i64)
+    }
+
+    // Reads a line holding a single byte for the ~ command in single byte mode, or the
+    // next whitespace-separated token of the buffered line, which must also be a
+    // single byte. At the end of the input, -1 is returned. May return the same
+    // errors as `read_character`.
+    fn read_single_byte(&mut self) -> Result<i64, Box<dyn StdError>> {
+        let mut line = Vec::new();
+
+        if self.buffered_line.is_empty() {
+            if self.read_input_line_bytes(&mut line)? == 0 {
+                return Ok(-1);
+            }
+        } else {
+            while self
+                .buffered_line
+                .front()
+                .is_some_and(|byte| byte.is_ascii_whitespace())
+            {
+                self.buffered_line.pop_front();
+            }
+
+            while let Some(&byte) = self.buffered_line.front() {
+                if byte.is_ascii_whitespace() {
+                    break;
+                }
+                line.push(byte);
+                self.buffered_line.pop_front();
+            }
+            self.discard_blank_buffered_line();
+        }
+
+        match line.trim_ascii() {
+            &[byte] => Ok(byte as i64),
+            input => Err(BefungeError::InvalidInput {
+                input: String::from_utf8_lossy(input).into_owned(),
+                expected: "single byte",
+            }
+            .into()),
+        }
+    }
+
+    // Reads the next character that is not whitespace from the buffered line, which
+    // must not be blank
+    fn read_buffered_character(&mut self) -> Result<char, Box<dyn StdError>> {
+        while self
+            .buffered_line
+            .front()
+            .is_some_and(|byte| byte.is_ascii_whitespace())
+        {
+            self.buffered_line.pop_front();
+        }
+
+        // The length of a UTF-8 sequence is given by its first byte
+        let length = match self.buffered_line.front() {
+            Some(0xf0..=0xff) => 4,
+            Some(0xe0..=0xef) => 3,
+            Some(0xc0..=0xdf) => 2,
+            _ => 1,
+        }
+        .min(self.buffered_line.len());
+
+        let character = String::from_utf8(self.buffered_line.drain(..length).collect())?
+            .chars()
+            .next()
+            .unwrap_or(' ');
+
+        self.discard_blank_buffered_line();
+        Ok(character)
+    }
+}
+
+// Creates the stack execution starts with, holding `options.initial_stack`
+fn initial_stack(options: &Options) -> Stack {
+    let mut stack = Stack::with_capacity(options.stack_capacity);
+    for &value in &options.initial_stack {
+        stack.push(value);
+    }
+    stack
+}
+
+// Removes every line of the code that starts with the comment character
+fn strip_comment_lines(code: &str, comment_char: char) -> String {
+    code.lines()
+        .filter(|line| !line.starts_with(comment_char))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Removes every label line (`;label NAME`) from the code, and returns the remaining
+// code along with the position of the cell below the `;` of the label named `label`
+fn strip_labels(code: &str, label: &str) -> Result<(String, Coord), BefungeError> {
+    let mut lines = Vec::new();
+    let mut position = None;
+
+    for line in code.lines() {
+        let trimmed = line.trim_start();
+
+        match trimmed.strip_prefix(";label ") {
+            Some(name) if name.trim() == label => {
+                if position.is_some() {
+                    return Err(BefungeError::Message(format!(
+                        "The label {} is defined more than once!",
+                        label
+                    )));
+                }
+
+                position = Some(Coord {
+                    x: (line.chars().count() - trimmed.chars().count()) as i64,
+                    y: lines.len() as i64,
+                });
+            }
+            Some(_) => (),
+            None => lines.push(line),
+        }
+    }
+
+    match position {
+        Some(position) => Ok((lines.join("\n"), position)),
+        None => Err(BefungeError::Message(format!(
+            "The label {} does not exist!",
+            label
+        ))),
+    }
 }
 
-// TODO: Convert errors to BefungeErrors
-fn convert_int_to_char(value: i64) -> Result<char, Box<dyn StdError>> {
-    if value < 0 || value > 255 {
-        return Err(BefungeError(format!(
-            "{} is not a valid ASCII value (between 0 and 255 inclusive)!",
-            value
-        ))
-        .into());
+// Divides b by a, rounding the quotient toward negative infinity. `a` must not be 0.
+// Like truncated division, i64::MIN / -1 wraps to i64::MIN.
+fn floored_division(b: i64, a: i64) -> i64 {
+    let quotient = b.wrapping_div(a);
+
+    if b.wrapping_rem(a) != 0 && (b < 0) != (a < 0) {
+        quotient - 1
+    } else {
+        quotient
     }
+}
 
-    std::char::from_u32(value as u32)
-        .ok_or_else(|| format!("Unable to convert ASCII value {} to a char", value).into())
+// Returns the remainder of b / a rounded toward negative infinity, which has the
+// sign of a, so that it matches floored_division
+fn floored_modulo(b: i64, a: i64) -> i64 {
+    let remainder = b.wrapping_rem(a);
+
+    if remainder != 0 && (remainder < 0) != (a < 0) {
+        remainder + a
+    } else {
+        remainder
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use befunge::samples;
     use std::io;
 
     // fn setup_interpreter(code: &str) -> Interpreter<io::Stdout, io::StdinLock> {
@@ -303,12 +1469,21 @@ mod tests {
     fn setup_interpreter<'a>(
         code: &str,
         input_data: Option<&'a [u8]>,
+    ) -> Interpreter<Vec<u8>, &'a [u8]> {
+        setup_interpreter_with_options(code, input_data, Options::default())
+    }
+
+    fn setup_interpreter_with_options<'a>(
+        code: &str,
+        input_data: Option<&'a [u8]>,
+        options: Options,
     ) -> Interpreter<Vec<u8>, &'a [u8]> {
         let output_handle: Vec<u8> = Vec::new();
         let input_handle = input_data.unwrap_or("".as_bytes());
 
         let mut interpreter =
-            Interpreter::new(code, output_handle, input_handle, None, None).unwrap();
+            Interpreter::with_options(code, output_handle, input_handle, None, None, options)
+                .unwrap();
         interpreter.execute().unwrap();
         interpreter
     }
@@ -366,6 +1541,35 @@ mod tests {
             assert!(interpreter.is_err());
         }
 
+        #[test]
+        fn test_initial_position_at_edge() {
+            for position in [Coord { x: 2, y: 0 }, Coord { x: 0, y: 2 }] {
+                let interpreter =
+                    Interpreter::new("12\n34", Vec::new(), "".as_bytes(), Some(position), None);
+
+                assert!(interpreter.is_err());
+            }
+        }
+
+        #[test]
+        fn test_initial_position_on_halt() {
+            let mut interpreter = Interpreter::new(
+                "5.@",
+                Vec::new(),
+                "".as_bytes(),
+                Some(Coord { x: 2, y: 0 }),
+                None,
+            )
+            .unwrap();
+
+            assert!(interpreter.execute().is_ok());
+            assert!(interpreter.output_handle.is_empty());
+            assert!(interpreter.stack.is_empty());
+
+            // Executing the @ itself counts as the only step
+            assert_eq!(interpreter.steps(), 1);
+        }
+
         #[test]
         fn test_initial_direction() {
             let input_handle = io::stdin();
@@ -384,107 +1588,1783 @@ mod tests {
             );
         }
 
-        #[test]
-        fn test_initial_direction_and_position() {
-            let input_handle = io::stdin();
-            let mut interpreter = Interpreter::new(
-                "5:.,@",
-                io::stdout(),
-                input_handle.lock(),
-                Some(Coord { x: 1, y: 0 }),
-                Some(Direction::Left),
-            )
-            .unwrap();
+        #[test]
+        fn test_initial_direction_and_position() {
+            let input_handle = io::stdin();
+            let mut interpreter = Interpreter::new(
+                "5:.,@",
+                io::stdout(),
+                input_handle.lock(),
+                Some(Coord { x: 1, y: 0 }),
+                Some(Direction::Left),
+            )
+            .unwrap();
+
+            assert_eq!(
+                interpreter.playfield.program_counter_position,
+                Coord { x: 1, y: 0 }
+            );
+            assert_eq!(
+                interpreter.playfield.program_counter_direction,
+                Direction::Left
+            );
+
+            interpreter.playfield.update_program_counter();
+            assert_eq!(interpreter.playfield.get_next_character(), '5');
+        }
+
+        #[test]
+        fn test_stack_capacity() {
+            let mut interpreter = Interpreter::with_options(
+                "\"zyxwvutsrqponmlkjihgfedcba\"@",
+                Vec::new(),
+                "".as_bytes(),
+                None,
+                None,
+                Options {
+                    stack_capacity: 64,
+                    ..Options::default()
+                },
+            )
+            .unwrap();
+            let initial_capacity = interpreter.stack.capacity();
+
+            interpreter.execute().unwrap();
+
+            // Reserving the capacity up front means the stack never reallocates
+            assert!(initial_capacity >= 64);
+            assert_eq!(interpreter.stack.capacity(), initial_capacity);
+            assert_eq!(interpreter.peak_stack_depth(), 26);
+        }
+
+        #[test]
+        fn test_comment_char() {
+            let interpreter = Interpreter::with_options(
+                "; A comment\n5.@\n;Another comment\n12",
+                Vec::new(),
+                "".as_bytes(),
+                None,
+                None,
+                Options {
+                    comment_char: Some(';'),
+                    ..Options::default()
+                },
+            )
+            .unwrap();
+
+            assert_eq!(
+                interpreter.playfield.code_map,
+                vec![['5', '.', '@'], ['1', '2', ' ']]
+            );
+            assert_eq!(interpreter.playfield.dimensions, Coord { x: 3, y: 2 });
+        }
+
+        #[test]
+        fn test_entry_label() {
+            let interpreter = Interpreter::with_options(
+                "1.@\n  ;label start\n  2.@\n ;label other\n 3.@",
+                Vec::new(),
+                "".as_bytes(),
+                None,
+                None,
+                Options {
+                    entry_label: Some("start".to_string()),
+                    ..Options::default()
+                },
+            )
+            .unwrap();
+
+            assert_eq!(
+                interpreter.playfield.program_counter_position,
+                Coord { x: 2, y: 1 }
+            );
+            assert_eq!(interpreter.playfield.dimensions, Coord { x: 5, y: 3 });
+        }
+
+        #[test]
+        fn test_missing_entry_label() {
+            let result = Interpreter::with_options(
+                "1.@\n;label start",
+                Vec::new(),
+                "".as_bytes(),
+                None,
+                None,
+                Options {
+                    entry_label: Some("missing".to_string()),
+                    ..Options::default()
+                },
+            );
+            assert!(result.is_err());
+
+            // A label on the last line marks no cell
+            let result = Interpreter::with_options(
+                "1.@\n;label start",
+                Vec::new(),
+                "".as_bytes(),
+                None,
+                None,
+                Options {
+                    entry_label: Some("start".to_string()),
+                    ..Options::default()
+                },
+            );
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn test_alternative_output_handle() {
+            let input_handle = io::stdin();
+            let mut output_handle: Vec<u8> = Vec::new();
+            let interpreter =
+                Interpreter::new("5:.,@", &mut output_handle, input_handle.lock(), None, None);
+            assert!(interpreter.is_ok());
+        }
+
+        #[test]
+        fn test_alternative_input_handle() {
+            let mut input = "input".as_bytes();
+            let interpreter = Interpreter::new("5:.,@", io::stdout(), &mut input, None, None);
+            assert!(interpreter.is_ok());
+        }
+    }
+
+    mod befunge_code {
+        use super::*;
+
+        mod example_programs {
+            use super::*;
+            // Alternative to integration tests (temporarily)
+
+            #[test]
+            fn test_empty_program() {
+                for code in &["", "\n\n"] {
+                    let mut interpreter = setup_interpreter(code, None);
+
+                    assert!(interpreter.output_handle.is_empty());
+                    assert_eq!(interpreter.steps, 0);
+                    assert_eq!(interpreter.step().unwrap(), StepResult::Halted);
+                }
+            }
+
+            #[test]
+            fn test_hello_world() {
+                let interpreter = setup_interpreter(samples::HELLO_WORLD, None);
+                assert_eq!(interpreter.output_handle, "Hello, World!\n".as_bytes());
+            }
+
+            #[test]
+            fn test_factorial() {
+                let interpreter = setup_interpreter(samples::FACTORIAL, Some("5".as_bytes()));
+                assert_eq!(interpreter.output_handle, "120 ".as_bytes());
+            }
+
+            #[test]
+            fn test_sieve_of_eratosthenes() {
+                let interpreter = setup_interpreter(samples::SIEVE_OF_ERATOSTHENES, None);
+
+                assert_eq!(
+                    interpreter.output_handle,
+                    "2 3 5 7 11 13 17 19 23 29 31 37 41 43 47 53 59 61 67 71 73 79 ".as_bytes()
+                );
+            }
+
+            #[test]
+            fn test_quine_one() {
+                let interpreter = setup_interpreter(samples::QUINE, None);
+                assert_eq!(interpreter.output_handle, samples::QUINE.as_bytes());
+            }
+
+            #[test]
+            fn test_quine_two() {
+                let interpreter = setup_interpreter(samples::MULTILINE_QUINE, None);
+                assert_eq!(
+                    interpreter.output_handle,
+                    format!("{} ", samples::MULTILINE_QUINE).as_bytes()
+                );
+            }
+
+            #[should_panic]
+            #[test]
+            fn test_quine_three() {
+                let interpreter = setup_interpreter(":0g,:\"~\"`#@_1+0\"Quines are Fun\">_", None);
+                assert_eq!(
+                    interpreter.output_handle,
+                    ":0g,:\"~\"`#@_1+0\"Quines are Fun\">_".as_bytes()
+                );
+            }
+        }
+
+        #[test]
+        fn test_execute_returns_stack() {
+            let mut interpreter =
+                Interpreter::new("123.@", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+            // The top of the stack is last
+            assert_eq!(interpreter.execute().unwrap(), [1, 2]);
+            assert_eq!(interpreter.stack_top_first(), [2, 1]);
+        }
+
+        #[test]
+        fn test_execute_with_stats() {
+            let mut interpreter =
+                Interpreter::new("5:.,@", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+            let stats = interpreter.execute_with_stats().unwrap();
+
+            assert_eq!(stats.steps, 5);
+            assert_eq!(stats.peak_stack_depth, 2);
+            assert_eq!(stats.bytes_written, 3);
+            assert_eq!(interpreter.output_handle, "5 \u{5}".as_bytes());
+        }
+
+        #[test]
+        fn test_output_events() {
+            let interpreter = setup_interpreter_with_options(
+                "5:.,@",
+                None,
+                Options {
+                    record_output_events: true,
+                    ..Options::default()
+                },
+            );
+
+            assert_eq!(interpreter.output_events(), [(3, b'5'), (3, b' '), (4, 5)]);
+        }
+
+        #[test]
+        fn test_output_checkpoints() {
+            let interpreter = setup_interpreter_with_options(
+                "\"A\"1.,2.@",
+                None,
+                Options {
+                    record_output_events: true,
+                    ..Options::default()
+                },
+            );
+
+            assert_eq!(
+                interpreter.output_checkpoints(),
+                [
+                    (5, b"1 ".to_vec()),
+                    (6, b"1 A".to_vec()),
+                    (8, b"1 A2 ".to_vec())
+                ]
+            );
+        }
+
+        #[test]
+        fn test_last_command() {
+            let mut interpreter =
+                Interpreter::new("\"a\"#1 @", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+            let mut commands = Vec::new();
+            while interpreter.step().unwrap() == StepResult::Continued {
+                commands.push(interpreter.last_command());
+            }
+            commands.push(interpreter.last_command());
+
+            assert_eq!(
+                commands,
+                [Some('"'), None, None, Some('#'), None, Some(' '), Some('@')]
+            );
+        }
+
+        #[test]
+        fn test_wrapped() {
+            let mut interpreter =
+                Interpreter::new("<@.5", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+            let mut wraps = Vec::new();
+            while interpreter.step().unwrap() == StepResult::Continued {
+                wraps.push(interpreter.wrapped());
+            }
+            wraps.push(interpreter.wrapped());
+
+            assert_eq!(wraps, [true, false, false, false]);
+        }
+
+        #[test]
+        fn test_stack_top_first() {
+            let interpreter = setup_interpreter("123@", None);
+            assert_eq!(interpreter.stack_top_first(), [3, 2, 1]);
+        }
+
+        #[test]
+        fn test_save_and_load_stack() {
+            let interpreter = setup_interpreter("523@", None);
+            let mut saved = Vec::new();
+            interpreter.save_stack(&mut saved).unwrap();
+            assert_eq!(saved, "5\n2\n3\n".as_bytes());
+
+            let mut interpreter =
+                Interpreter::new("+.@", Vec::new(), "".as_bytes(), None, None).unwrap();
+            interpreter.load_stack(&saved[..]).unwrap();
+            let stack = interpreter.execute().unwrap();
+
+            assert_eq!(interpreter.output_handle, "5 ".as_bytes());
+            assert_eq!(stack, [5]);
+        }
+
+        #[test]
+        fn test_load_invalid_stack() {
+            let mut interpreter = setup_interpreter("1@", None);
+            let err = interpreter.load_stack("2 x".as_bytes()).unwrap_err();
+            match err.downcast_ref::<BefungeError>() {
+                Some(BefungeError::InvalidInput { input, expected }) => {
+                    assert_eq!(input, "x");
+                    assert_eq!(*expected, "integer");
+                }
+                _ => panic!("Expected invalid input, got {:?}", err),
+            }
+            assert_eq!(interpreter.stack_top_first(), [1]);
+        }
+
+        mod register_command {
+            use super::*;
+
+            fn setup_doubling_interpreter(code: &str) -> Interpreter<Vec<u8>, &[u8]> {
+                let mut interpreter =
+                    Interpreter::new(code, Vec::new(), "".as_bytes(), None, None).unwrap();
+                interpreter
+                    .register_command(
+                        'D',
+                        Box::new(|stack: &mut Stack, _: &mut Playfield| {
+                            let value = stack.pop();
+                            stack.push(value * 2);
+                            Ok(())
+                        }),
+                    )
+                    .unwrap();
+
+                interpreter
+            }
+
+            #[test]
+            fn test_custom_command() {
+                let mut interpreter = setup_doubling_interpreter("5DD.@");
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "20 ".as_bytes());
+            }
+
+            #[test]
+            fn test_existing_command() {
+                let mut interpreter = setup_doubling_interpreter("@");
+                let result = interpreter
+                    .register_command('+', Box::new(|_: &mut Stack, _: &mut Playfield| Ok(())));
+                assert!(result.is_err());
+            }
+
+            #[test]
+            fn test_unregistered_command() {
+                let mut interpreter = setup_doubling_interpreter("5E.@");
+                assert!(interpreter.execute().is_err());
+            }
+
+            #[test]
+            fn test_out_of_bounds_program_counter() {
+                let mut interpreter = setup_doubling_interpreter("M@");
+                interpreter
+                    .register_command(
+                        'M',
+                        Box::new(|_: &mut Stack, playfield: &mut Playfield| {
+                            playfield.program_counter_position = Coord { x: 50, y: 0 };
+                            Ok(())
+                        }),
+                    )
+                    .unwrap();
+                assert!(interpreter.step().is_err());
+                assert_eq!(
+                    interpreter.playfield().program_counter_position,
+                    Coord { x: 0, y: 0 }
+                );
+
+                // Stepping again runs the handler again instead of panicking
+                assert!(interpreter.step().is_err());
+            }
+
+            #[test]
+            fn test_resized_playfield() {
+                let mut interpreter = setup_doubling_interpreter("R@");
+                interpreter
+                    .register_command(
+                        'R',
+                        Box::new(|_: &mut Stack, playfield: &mut Playfield| {
+                            playfield.resize(5, 3, ' ')?;
+                            Ok(())
+                        }),
+                    )
+                    .unwrap();
+                assert!(interpreter.step().is_err());
+                assert_eq!(interpreter.playfield().dimensions, Coord { x: 2, y: 1 });
+                assert_eq!(interpreter.playfield().code_map, vec![vec!['R', '@']]);
+            }
+        }
+
+        mod null_padding {
+            use super::*;
+
+            fn setup_null_padded_interpreter(code: &str) -> Interpreter<Vec<u8>, &[u8]> {
+                let options = Options {
+                    padding: Padding::Null,
+                    ..Options::default()
+                };
+
+                setup_interpreter_with_options(code, None, options)
+            }
+
+            #[test]
+            fn test_get_padding() {
+                let interpreter = setup_null_padded_interpreter("11g.@\n1");
+                assert_eq!(interpreter.output_handle, "0 ".as_bytes());
+            }
+
+            #[test]
+            fn test_execute_padding() {
+                let interpreter = setup_null_padded_interpreter("v\n\n>5.@");
+                assert_eq!(interpreter.output_handle, "5 ".as_bytes());
+            }
+        }
+
+        mod set_program_counter {
+            use super::*;
+
+            #[test]
+            fn test_basic() {
+                let mut interpreter =
+                    Interpreter::new("5@.3", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+                interpreter
+                    .set_program_counter(Coord { x: 3, y: 0 }, Direction::Left)
+                    .unwrap();
+                interpreter.step().unwrap();
+
+                assert_eq!(interpreter.stack.as_slice(), [3]);
+                assert_eq!(
+                    interpreter.playfield.program_counter_position,
+                    Coord { x: 2, y: 0 }
+                );
+
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "3 ".as_bytes());
+            }
+
+            #[test]
+            fn test_out_of_bounds() {
+                let mut interpreter =
+                    Interpreter::new("5@.3", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+                assert!(interpreter
+                    .set_program_counter(Coord { x: 4, y: 0 }, Direction::Right)
+                    .is_err());
+                assert!(interpreter
+                    .set_program_counter(Coord { x: 0, y: -1 }, Direction::Right)
+                    .is_err());
+                assert_eq!(
+                    interpreter.playfield.program_counter_position,
+                    Coord { x: 0, y: 0 }
+                );
+            }
+        }
+
+        mod step {
+            use super::*;
+
+            #[test]
+            fn test_single_steps() {
+                let mut interpreter =
+                    Interpreter::new("5:.,@", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+                let expected: [(&[i64], i64); 4] = [(&[5], 1), (&[5, 5], 2), (&[5], 3), (&[], 4)];
+                for &(stack, x) in &expected {
+                    assert_eq!(interpreter.step().unwrap(), StepResult::Continued);
+                    assert_eq!(interpreter.stack.as_slice(), stack);
+                    assert_eq!(
+                        interpreter.playfield.program_counter_position,
+                        Coord { x, y: 0 }
+                    );
+                }
+
+                assert_eq!(interpreter.step().unwrap(), StepResult::Halted);
+                assert_eq!(
+                    interpreter.playfield.program_counter_position,
+                    Coord { x: 4, y: 0 }
+                );
+                assert_eq!(interpreter.output_handle, "5 \u{5}".as_bytes());
+            }
+
+            #[test]
+            fn test_stack_between_steps() {
+                let mut interpreter =
+                    Interpreter::new("55+@", Vec::new(), "".as_bytes(), None, None).unwrap();
+                assert!(interpreter.stack().is_empty());
+
+                interpreter.step().unwrap();
+                assert_eq!(interpreter.stack(), [5]);
+                interpreter.step().unwrap();
+                assert_eq!(interpreter.stack(), [5, 5]);
+                interpreter.step().unwrap();
+                assert_eq!(interpreter.stack(), [10]);
+
+                assert_eq!(interpreter.step().unwrap(), StepResult::Halted);
+                assert_eq!(interpreter.stack(), [10]);
+            }
+
+            #[test]
+            fn test_modes_across_steps() {
+                let mut interpreter =
+                    Interpreter::new("\"a\"#5@", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+                let expected = [
+                    Mode::String,
+                    Mode::String,
+                    Mode::Command,
+                    Mode::Bridge,
+                    Mode::Command,
+                ];
+                for &mode in &expected {
+                    assert_eq!(interpreter.step().unwrap(), StepResult::Continued);
+                    assert_eq!(interpreter.mode(), mode);
+                }
+
+                assert_eq!(interpreter.step().unwrap(), StepResult::Halted);
+                assert_eq!(interpreter.stack.as_slice(), ['a' as i64]);
+            }
+        }
+
+        mod seed {
+            use super::*;
+
+            fn run_with_seed(seed: u64) -> Vec<u8> {
+                let options = Options {
+                    seed: Some(seed),
+                    ..Options::default()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    include_str!("../../tests/random_digits.bf"),
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    options,
+                )
+                .unwrap();
+
+                interpreter.execute().unwrap();
+                interpreter.output_handle
+            }
+
+            #[test]
+            fn test_same_seed_same_output() {
+                let output = run_with_seed(42);
+
+                assert_eq!(output.len(), 20);
+                assert_eq!(run_with_seed(42), output);
+            }
+        }
+
+        mod bridge {
+            use super::*;
+
+            #[test]
+            fn test_skips_halt() {
+                let interpreter = setup_interpreter("#@5.@", None);
+                assert_eq!(interpreter.output_handle, "5 ".as_bytes());
+            }
+
+            #[test]
+            fn test_skips_halt_across_edge() {
+                let mut interpreter = Interpreter::new(
+                    "@5.@#",
+                    Vec::new(),
+                    "".as_bytes(),
+                    Some(Coord { x: 4, y: 0 }),
+                    None,
+                )
+                .unwrap();
+
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "5 ".as_bytes());
+            }
+
+            #[test]
+            fn test_steps_after_execute() {
+                let interpreter = setup_interpreter("5:.,@", None);
+                assert_eq!(interpreter.steps(), 5);
+            }
+
+            #[test]
+            fn test_halt_after_bridge() {
+                let mut interpreter =
+                    Interpreter::new("#5@.@", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+                interpreter.execute().unwrap();
+                assert!(interpreter.output_handle.is_empty());
+                assert_eq!(interpreter.steps(), 3);
+            }
+        }
+
+        mod max_output_lines {
+            use super::*;
+
+            fn setup_line_limited_interpreter(code: &str) -> Interpreter<Vec<u8>, &[u8]> {
+                let options = Options {
+                    max_output_lines: Some(2),
+                    ..Options::default()
+                };
+
+                setup_interpreter_with_options(code, None, options)
+            }
+
+            #[test]
+            fn test_unbounded_output() {
+                let interpreter = setup_line_limited_interpreter("1>:.55+,1+v\n ^        <");
+                assert_eq!(interpreter.output_handle, "1 \n2 \n".as_bytes());
+            }
+
+            #[test]
+            fn test_drops_output_after_last_line() {
+                let interpreter = setup_line_limited_interpreter("\"c\"55+\"b\"55+\"a\",,,,,@");
+                assert_eq!(interpreter.output_handle, "a\nb\n".as_bytes());
+            }
+
+            #[test]
+            fn test_fewer_lines() {
+                let interpreter = setup_line_limited_interpreter("55+\"a\",,5.@");
+                assert_eq!(interpreter.output_handle, "a\n5 ".as_bytes());
+            }
+        }
+
+        #[test]
+        fn test_initial_stack() {
+            let options = Options {
+                initial_stack: vec![1, 2, 3],
+                ..Options::default()
+            };
+            let mut interpreter =
+                Interpreter::with_options("-.@", Vec::new(), "".as_bytes(), None, None, options)
+                    .unwrap();
+            let stack = interpreter.execute().unwrap();
+
+            assert_eq!(interpreter.output_handle, "-1 ".as_bytes());
+            assert_eq!(stack, [1]);
+        }
+
+        mod programmatic_input {
+            use super::*;
+
+            fn setup_programmatic_interpreter(code: &str) -> Interpreter<Vec<u8>, &[u8]> {
+                let options = Options {
+                    programmatic_input: true,
+                    ..Options::default()
+                };
+
+                Interpreter::with_options(code, Vec::new(), "".as_bytes(), None, None, options)
+                    .unwrap()
+            }
+
+            #[test]
+            fn test_input_after_idle_polls() {
+                let mut interpreter = setup_programmatic_interpreter("&&+.@");
+
+                // Polling without input neither executes anything nor moves on
+                for _ in 0..5 {
+                    assert_eq!(interpreter.step().unwrap(), StepResult::NeedsInput);
+                }
+                assert_eq!(interpreter.steps(), 0);
+                assert_eq!(
+                    interpreter.playfield.program_counter_position,
+                    Coord { x: 0, y: 0 }
+                );
+
+                // A partial line is kept until the rest of it arrives
+                interpreter.provide_input("1").unwrap();
+                assert_eq!(interpreter.step().unwrap(), StepResult::NeedsInput);
+                interpreter.provide_input("2\n").unwrap();
+                assert_eq!(interpreter.step().unwrap(), StepResult::Continued);
+
+                for _ in 0..3 {
+                    assert_eq!(interpreter.step().unwrap(), StepResult::NeedsInput);
+                }
+                interpreter.provide_input("30\n").unwrap();
+
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "42 ".as_bytes());
+                assert_eq!(interpreter.steps(), 5);
+            }
+
+            #[test]
+            fn test_input_provided_ahead() {
+                let mut interpreter = setup_programmatic_interpreter("~~,,@");
+                interpreter.provide_input("a\nb\n").unwrap();
+
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "ba".as_bytes());
+            }
+
+            #[test]
+            fn test_execute_without_input() {
+                let mut interpreter = setup_programmatic_interpreter("&.@");
+                let err = interpreter.execute().unwrap_err();
+
+                match err.downcast_ref::<BefungeError>() {
+                    Some(BefungeError::InputRequired) => (),
+                    _ => panic!("Expected input to be required, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_stream_input() {
+                let mut interpreter =
+                    Interpreter::new("&.@", Vec::new(), "5".as_bytes(), None, None).unwrap();
+                assert!(interpreter.provide_input("5\n").is_err());
+            }
+        }
+
+        mod run_slice {
+            use super::*;
+
+            #[test]
+            fn test_run_to_completion() {
+                let mut interpreter =
+                    Interpreter::new(samples::HELLO_WORLD, Vec::new(), "".as_bytes(), None, None)
+                        .unwrap();
+
+                let mut slices = 1;
+                while interpreter.run_slice(10).unwrap() == StepResult::Continued {
+                    slices += 1;
+                }
+
+                assert_eq!(interpreter.output_handle, "Hello, World!\n".as_bytes());
+                assert_eq!(slices, interpreter.steps().div_ceil(10));
+            }
+
+            #[test]
+            fn test_needs_input() {
+                let options = Options {
+                    programmatic_input: true,
+                    ..Options::default()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    "12&.@",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    options,
+                )
+                .unwrap();
+
+                assert_eq!(interpreter.run_slice(10).unwrap(), StepResult::NeedsInput);
+                assert_eq!(interpreter.steps(), 2);
+            }
+
+            #[test]
+            fn test_needs_input_for_iterate() {
+                let options = Options {
+                    programmatic_input: true,
+                    funge98_iterate: true,
+                    ..Options::default()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    "3k&...@",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    options,
+                )
+                .unwrap();
+
+                // The count stays on the stack until the input arrives
+                assert_eq!(interpreter.run_slice(10).unwrap(), StepResult::NeedsInput);
+                assert_eq!(interpreter.steps(), 1);
+                assert_eq!(interpreter.stack(), [3]);
+
+                // Once the input runs out partway, k resumes with the iterations left
+                interpreter.provide_input("1\n").unwrap();
+                assert_eq!(interpreter.run_slice(10).unwrap(), StepResult::NeedsInput);
+                assert_eq!(interpreter.stack(), [1, 2]);
+
+                interpreter.provide_input("2\n3\n").unwrap();
+                assert_eq!(interpreter.run_slice(20).unwrap(), StepResult::Halted);
+                assert_eq!(interpreter.output_handle, "3 2 1 ".as_bytes());
+            }
+        }
+
+        mod halts_within {
+            use super::*;
+
+            #[test]
+            fn test_halting_program() {
+                let mut interpreter = Interpreter::new(
+                    "64+\"!dlroW ,olleH\">:#,_@",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                )
+                .unwrap();
+
+                assert!(interpreter.halts_within(1000));
+                assert_eq!(interpreter.output_handle, "Hello, World!\n".as_bytes());
+            }
+
+            #[test]
+            fn test_infinite_loop() {
+                let mut interpreter =
+                    Interpreter::new("><", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+                assert!(!interpreter.halts_within(1000));
+                assert_eq!(interpreter.steps(), 1000);
+            }
+
+            #[test]
+            fn test_error() {
+                let mut interpreter =
+                    Interpreter::new("50/@", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+                assert!(!interpreter.halts_within(1000));
+            }
+        }
+
+        mod output_transform {
+            use super::*;
+
+            #[test]
+            fn test_uppercase() {
+                let mut interpreter =
+                    Interpreter::new("\"olleh\",,,,,@", Vec::new(), "".as_bytes(), None, None)
+                        .unwrap();
+                interpreter
+                    .set_output_transform(Box::new(|byte: u8| Some(byte.to_ascii_uppercase())));
+
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "HELLO".as_bytes());
+            }
+
+            #[test]
+            fn test_dropped_bytes() {
+                let mut interpreter =
+                    Interpreter::new("\"olleh\",,,,,@", Vec::new(), "".as_bytes(), None, None)
+                        .unwrap();
+                interpreter.set_output_transform(Box::new(|byte: u8| match byte {
+                    b'l' => None,
+                    _ => Some(byte),
+                }));
+
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "heo".as_bytes());
+            }
+        }
+
+        mod on_stack_change {
+            use super::*;
+            use std::cell::RefCell;
+            use std::rc::Rc;
+
+            #[test]
+            fn test_called_after_changes() {
+                let snapshots = Rc::new(RefCell::new(Vec::new()));
+                let recorded = Rc::clone(&snapshots);
+
+                let mut interpreter =
+                    Interpreter::new("12+. $@", Vec::new(), "".as_bytes(), None, None).unwrap();
+                interpreter.set_on_stack_change(Box::new(move |stack: &[i64]| {
+                    recorded.borrow_mut().push(stack.to_vec())
+                }));
+
+                interpreter.execute().unwrap();
+
+                // The space, the pop of an empty stack and the halt change nothing
+                assert_eq!(
+                    *snapshots.borrow(),
+                    vec![vec![1], vec![1, 2], vec![3], vec![]]
+                );
+            }
+
+            #[test]
+            fn test_halt_inside_iterate() {
+                let snapshots = Rc::new(RefCell::new(Vec::new()));
+                let recorded = Rc::clone(&snapshots);
+
+                let options = Options {
+                    funge98_iterate: true,
+                    ..Options::default()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    "91k@",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    options,
+                )
+                .unwrap();
+                interpreter.set_on_stack_change(Box::new(move |stack: &[i64]| {
+                    recorded.borrow_mut().push(stack.to_vec())
+                }));
+
+                interpreter.execute().unwrap();
+
+                assert_eq!(*snapshots.borrow(), vec![vec![9], vec![9, 1], vec![9]]);
+            }
+        }
+
+        mod max_steps {
+            use super::*;
+
+            fn setup_limited_interpreter(code: &str) -> Interpreter<Vec<u8>, &[u8]> {
+                let options = Options {
+                    max_steps: Some(10),
+                    ..Options::default()
+                };
+
+                Interpreter::with_options(code, Vec::new(), "".as_bytes(), None, None, options)
+                    .unwrap()
+            }
+
+            #[test]
+            fn test_infinite_loop() {
+                let mut interpreter = setup_limited_interpreter(">");
+                let err = interpreter.execute().unwrap_err();
+
+                match err.downcast_ref::<BefungeError>() {
+                    Some(BefungeError::StepLimitExceeded { limit }) => assert_eq!(*limit, 10),
+                    _ => panic!("Expected the step limit to be exceeded, got {:?}", err),
+                }
+                assert_eq!(interpreter.steps(), 10);
+            }
+
+            #[test]
+            fn test_halting_within_limit() {
+                let mut interpreter = setup_limited_interpreter("1.@");
+                assert!(interpreter.execute().is_ok());
+                assert_eq!(interpreter.output_handle, "1 ".as_bytes());
+            }
+        }
+
+        mod strict_halt {
+            use super::*;
+
+            fn setup_strict_interpreter(code: &str) -> Interpreter<Vec<u8>, &[u8]> {
+                let options = Options {
+                    max_consecutive_spaces: Some(10),
+                    ..Options::default()
+                };
+
+                Interpreter::with_options(code, Vec::new(), "".as_bytes(), None, None, options)
+                    .unwrap()
+            }
+
+            #[test]
+            fn test_wandering_into_padding() {
+                let mut interpreter = setup_strict_interpreter("v\n>                    \n@");
+                let err = interpreter.execute().unwrap_err();
+
+                match err.downcast_ref::<BefungeError>() {
+                    Some(BefungeError::ConsecutiveSpacesExceeded { limit, coord }) => {
+                        assert_eq!(*limit, 10);
+                        assert_eq!(coord.y, 1);
+                    }
+                    _ => panic!("Expected too many consecutive spaces, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_short_runs_of_spaces() {
+                let mut interpreter = setup_strict_interpreter("5     .     @");
+                assert!(interpreter.execute().is_ok());
+                assert_eq!(interpreter.output_handle, "5 ".as_bytes());
+            }
+
+            #[test]
+            fn test_spaces_in_string_mode() {
+                let mut interpreter = setup_strict_interpreter("\"                    \"@");
+                assert!(interpreter.execute().is_ok());
+            }
+
+            #[test]
+            fn test_wandering_into_null_padding() {
+                let options = Options {
+                    max_consecutive_spaces: Some(5),
+                    padding: Padding::Null,
+                    ..Options::default()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    "v234567890\n>\n",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    options,
+                )
+                .unwrap();
+                assert!(interpreter.execute().is_err());
+            }
+        }
+
+        mod stuck_in_empty_region {
+            use super::*;
+
+            #[test]
+            fn test_moving_through_padding() {
+                let mut interpreter =
+                    Interpreter::new("1.@\n\n@", Vec::new(), "".as_bytes(), None, None).unwrap();
+                interpreter
+                    .set_program_counter(Coord { x: 0, y: 1 }, Direction::Right)
+                    .unwrap();
+                let err = interpreter.execute().unwrap_err();
+
+                match err.downcast_ref::<BefungeError>() {
+                    Some(BefungeError::StuckInEmptyRegion { coord }) => assert_eq!(coord.y, 1),
+                    _ => panic!("Expected a stuck program counter, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_moving_through_null_padding() {
+                let options = Options {
+                    padding: Padding::Null,
+                    ..Options::default()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    "1.@\n\n@",
+                    Vec::new(),
+                    "".as_bytes(),
+                    Some(Coord { x: 0, y: 1 }),
+                    None,
+                    options,
+                )
+                .unwrap();
+                let err = interpreter.execute().unwrap_err();
+
+                match err.downcast_ref::<BefungeError>() {
+                    Some(BefungeError::StuckInEmptyRegion { coord }) => assert_eq!(coord.y, 1),
+                    _ => panic!("Expected a stuck program counter, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_long_runs_of_spaces() {
+                let mut interpreter =
+                    Interpreter::new("5      .@", Vec::new(), "".as_bytes(), None, None).unwrap();
+                assert!(interpreter.execute().is_ok());
+                assert_eq!(interpreter.output_handle, "5 ".as_bytes());
+            }
+        }
+
+        mod error_kinds {
+            use super::*;
+
+            fn run_to_error(code: &str, input: &str) -> BefungeError {
+                let mut interpreter =
+                    Interpreter::new(code, Vec::new(), input.as_bytes(), None, None).unwrap();
+                let err = interpreter.execute().unwrap_err();
+
+                match err.downcast::<BefungeError>() {
+                    Ok(err) => *err,
+                    Err(err) => panic!("Expected a BefungeError, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_division_by_zero() {
+                match run_to_error("60/@", "") {
+                    BefungeError::DivisionByZero { dividend } => assert_eq!(dividend, 6),
+                    err => panic!("Expected a division by zero, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_modulo_by_zero() {
+                match run_to_error("70%@", "") {
+                    BefungeError::ModuloByZero { dividend } => assert_eq!(dividend, 7),
+                    err => panic!("Expected a modulo by zero, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_out_of_bounds() {
+                match run_to_error("99g@", "") {
+                    BefungeError::OutOfBounds { coord } => {
+                        assert_eq!(coord, Coord { x: 9, y: 9 })
+                    }
+                    err => panic!("Expected an out of bounds access, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_invalid_command() {
+                match run_to_error("5Z@", "") {
+                    BefungeError::InvalidCommand { command, coord } => {
+                        assert_eq!(command, 'Z');
+                        assert_eq!(coord, Coord { x: 1, y: 0 });
+                    }
+                    err => panic!("Expected an invalid command, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_invalid_ascii_value() {
+                match run_to_error("88*8*00p@", "") {
+                    BefungeError::InvalidAsciiValue(value) => assert_eq!(value, 512),
+                    err => panic!("Expected an invalid ASCII value, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_invalid_input() {
+                match run_to_error("&@", "abc\n") {
+                    BefungeError::InvalidInput { input, expected } => {
+                        assert_eq!(input, "abc");
+                        assert_eq!(expected, "integer");
+                    }
+                    err => panic!("Expected invalid input, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_display() {
+                let err = BefungeError::InvalidCommand {
+                    command: 'Z',
+                    coord: Coord { x: 1, y: 0 },
+                };
+                assert_eq!(err.to_string(), "Z at (1, 0) is not a valid command!");
+                assert_eq!(
+                    BefungeError::DivisionByZero { dividend: 3 }.to_string(),
+                    "Cannot divide 3 by 0!"
+                );
+            }
+        }
+
+        mod assertions {
+            use super::*;
+
+            fn setup_extended_interpreter(code: &str) -> Interpreter<Vec<u8>, &[u8]> {
+                let options = Options {
+                    extended_commands: true,
+                    ..Options::default()
+                };
+
+                Interpreter::with_options(code, Vec::new(), "".as_bytes(), None, None, options)
+                    .unwrap()
+            }
+
+            #[test]
+            fn test_passing_assertion() {
+                let mut interpreter = setup_extended_interpreter("23+5A1.@");
+                assert!(interpreter.execute().is_ok());
+                assert_eq!(interpreter.output_handle, "1 ".as_bytes());
+            }
+
+            #[test]
+            fn test_failing_assertion() {
+                let mut interpreter = setup_extended_interpreter("23+6A1.@");
+                let err = interpreter.execute().unwrap_err();
+
+                match err.downcast_ref::<BefungeError>() {
+                    Some(BefungeError::AssertionFailed { expected, actual }) => {
+                        assert_eq!(*expected, 6);
+                        assert_eq!(*actual, 5);
+                    }
+                    _ => panic!("Expected an assertion failure, got {:?}", err),
+                }
+                assert!(interpreter.output_handle.is_empty());
+            }
+
+            #[test]
+            fn test_disabled_by_default() {
+                let mut interpreter =
+                    Interpreter::new("55A@", Vec::new(), "".as_bytes(), None, None).unwrap();
+                assert!(interpreter.execute().is_err());
+            }
+        }
+
+        mod crlf {
+            use super::*;
+
+            fn setup_crlf_interpreter(code: &str, crlf: CrlfMode) -> Interpreter<Vec<u8>, &[u8]> {
+                let options = Options {
+                    crlf,
+                    ..Options::default()
+                };
+
+                Interpreter::with_options(code, Vec::new(), "".as_bytes(), None, None, options)
+                    .unwrap()
+            }
+
+            #[test]
+            fn test_pass_through() {
+                let mut interpreter = setup_crlf_interpreter("94+,55+,@", CrlfMode::PassThrough);
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "\r\n".as_bytes());
+            }
+
+            #[test]
+            fn test_coalesce() {
+                let mut interpreter = setup_crlf_interpreter("94+,55+,@", CrlfMode::Coalesce);
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "\n".as_bytes());
+            }
+
+            #[test]
+            fn test_coalesce_lone_carriage_returns() {
+                let mut interpreter = setup_crlf_interpreter("94+,\"a\",94+,@", CrlfMode::Coalesce);
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "\ra\r".as_bytes());
+            }
+        }
+
+        mod funge98_comments {
+            use super::*;
+
+            fn comment_options() -> Options {
+                Options {
+                    funge98_comments: true,
+                    ..Options::default()
+                }
+            }
+
+            #[test]
+            fn test_basic() {
+                let interpreter =
+                    setup_interpreter_with_options(";ignored;5.@", None, comment_options());
+                assert_eq!(interpreter.output_handle, "5 ".as_bytes());
+            }
+
+            #[test]
+            fn test_commands_in_comment() {
+                let interpreter =
+                    setup_interpreter_with_options("1;@.\"v;.@", None, comment_options());
+                assert_eq!(interpreter.output_handle, "1 ".as_bytes());
+            }
+
+            #[test]
+            fn test_disabled_by_default() {
+                let mut interpreter =
+                    Interpreter::new(";ignored;5.@", Vec::new(), "".as_bytes(), None, None)
+                        .unwrap();
+                assert!(interpreter.execute().is_err());
+            }
+        }
+
+        mod funge98_fetch {
+            use super::*;
+
+            fn fetch_options() -> Options {
+                Options {
+                    funge98_fetch: true,
+                    ..Options::default()
+                }
+            }
+
+            #[test]
+            fn test_basic() {
+                let interpreter = setup_interpreter_with_options("'A,@", None, fetch_options());
+                assert_eq!(interpreter.output_handle, "A".as_bytes());
+            }
+
+            #[test]
+            fn test_fetch_command() {
+                // The fetched @ is pushed rather than executed
+                let interpreter = setup_interpreter_with_options("'@.@", None, fetch_options());
+                assert_eq!(interpreter.output_handle, "64 ".as_bytes());
+            }
+
+            #[test]
+            fn test_direction_and_wraparound() {
+                let interpreter = setup_interpreter_with_options("<@,B'", None, fetch_options());
+                assert_eq!(interpreter.output_handle, "B".as_bytes());
+
+                // The fetch itself wraps around to the first cell of the row
+                let mut interpreter = Interpreter::with_options(
+                    "D,@'",
+                    Vec::new(),
+                    "".as_bytes(),
+                    Some(Coord { x: 3, y: 0 }),
+                    None,
+                    fetch_options(),
+                )
+                .unwrap();
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "D".as_bytes());
+
+                let interpreter =
+                    setup_interpreter_with_options("v\n'\nC\n,\n@", None, fetch_options());
+                assert_eq!(interpreter.output_handle, "C".as_bytes());
+            }
+
+            #[test]
+            fn test_wrapped() {
+                let mut interpreter = Interpreter::with_options(
+                    "D,@'",
+                    Vec::new(),
+                    "".as_bytes(),
+                    Some(Coord { x: 3, y: 0 }),
+                    None,
+                    fetch_options(),
+                )
+                .unwrap();
+                interpreter.step().unwrap();
+                assert!(interpreter.wrapped());
+                assert_eq!(
+                    interpreter.playfield().program_counter_position,
+                    Coord { x: 1, y: 0 }
+                );
+
+                interpreter.step().unwrap();
+                assert!(!interpreter.wrapped());
+            }
+
+            #[test]
+            fn test_integer_cells() {
+                // The cell holds -5 after p, which ' fetches unchanged
+                let options = Options {
+                    integer_cells: true,
+                    ..fetch_options()
+                };
+                let interpreter = setup_interpreter_with_options("05-70p'X.@", None, options);
+                assert_eq!(interpreter.output_handle, "-5 ".as_bytes());
+            }
+
+            #[test]
+            fn test_disabled_by_default() {
+                let mut interpreter =
+                    Interpreter::new("'A,@", Vec::new(), "".as_bytes(), None, None).unwrap();
+                assert!(interpreter.execute().is_err());
+            }
+        }
+
+        mod funge98_iterate {
+            use super::*;
+
+            fn iterate_options() -> Options {
+                Options {
+                    funge98_iterate: true,
+                    ..Options::default()
+                }
+            }
+
+            #[test]
+            fn test_zero_skips_the_next_cell() {
+                let interpreter = setup_interpreter_with_options("50k.@", None, iterate_options());
+                assert!(interpreter.output_handle.is_empty());
+                assert_eq!(interpreter.stack(), [5]);
+            }
+
+            #[test]
+            fn test_once() {
+                let interpreter = setup_interpreter_with_options("51k.@", None, iterate_options());
+                assert_eq!(interpreter.output_handle, "5 ".as_bytes());
+            }
+
+            #[test]
+            fn test_three_times() {
+                let interpreter =
+                    setup_interpreter_with_options("1233k.@", None, iterate_options());
+                assert_eq!(interpreter.output_handle, "3 2 1 ".as_bytes());
+                assert!(interpreter.stack().is_empty());
+            }
+
+            #[test]
+            fn test_wraparound() {
+                // The cell after k is found by wrapping around to the start of the row
+                let mut interpreter = Interpreter::with_options(
+                    ".@122k",
+                    Vec::new(),
+                    "".as_bytes(),
+                    Some(Coord { x: 2, y: 0 }),
+                    None,
+                    iterate_options(),
+                )
+                .unwrap();
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "2 1 ".as_bytes());
+            }
+
+            #[test]
+            fn test_wrapped() {
+                let mut interpreter = Interpreter::with_options(
+                    ".@122k",
+                    Vec::new(),
+                    "".as_bytes(),
+                    Some(Coord { x: 2, y: 0 }),
+                    None,
+                    iterate_options(),
+                )
+                .unwrap();
+                for _ in 0..3 {
+                    interpreter.step().unwrap();
+                    assert!(!interpreter.wrapped());
+                }
+
+                interpreter.step().unwrap();
+                assert!(interpreter.wrapped());
+            }
+
+            #[test]
+            fn test_halt() {
+                let interpreter = setup_interpreter_with_options("1k@5.@", None, iterate_options());
+                assert!(interpreter.output_handle.is_empty());
+            }
+
+            #[test]
+            fn test_alt_halt() {
+                let options = Options {
+                    alt_halt: Some('H'),
+                    ..iterate_options()
+                };
+                let interpreter = setup_interpreter_with_options("1kH5.@", None, options);
+                assert!(interpreter.output_handle.is_empty());
+            }
+
+            #[test]
+            fn test_custom_command() {
+                let mut interpreter = Interpreter::with_options(
+                    "52kD.@",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    iterate_options(),
+                )
+                .unwrap();
+                interpreter
+                    .register_command(
+                        'D',
+                        Box::new(|stack: &mut Stack, _: &mut Playfield| {
+                            let value = stack.pop();
+                            stack.push(value * 2);
+                            Ok(())
+                        }),
+                    )
+                    .unwrap();
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.output_handle, "20 ".as_bytes());
+            }
+
+            #[test]
+            fn test_iterations_are_steps() {
+                let interpreter = setup_interpreter_with_options("3k1@", None, iterate_options());
+                assert_eq!(interpreter.steps(), 6);
+            }
+
+            #[test]
+            fn test_max_steps() {
+                let options = Options {
+                    max_steps: Some(10),
+                    ..iterate_options()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    "99*:*:*k1@",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    options,
+                )
+                .unwrap();
+                let err = interpreter.execute().unwrap_err();
+
+                match err.downcast_ref::<BefungeError>() {
+                    Some(BefungeError::StepLimitExceeded { limit }) => assert_eq!(*limit, 10),
+                    _ => panic!("Expected the step limit to be exceeded, got {:?}", err),
+                }
+                assert_eq!(interpreter.steps(), 10);
+            }
+
+            #[test]
+            fn test_nested() {
+                let mut interpreter = Interpreter::with_options(
+                    "11kk@",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    iterate_options(),
+                )
+                .unwrap();
+                let err = interpreter.execute().unwrap_err();
+
+                match err.downcast_ref::<BefungeError>() {
+                    Some(BefungeError::NotIterable { command, coord }) => {
+                        assert_eq!(*command, 'k');
+                        assert_eq!(*coord, Coord { x: 3, y: 0 });
+                    }
+                    _ => panic!("Expected a command that cannot be iterated, got {:?}", err),
+                }
+            }
+
+            #[test]
+            fn test_disabled_by_default() {
+                let mut interpreter =
+                    Interpreter::new("11k.@", Vec::new(), "".as_bytes(), None, None).unwrap();
+                assert!(interpreter.execute().is_err());
+            }
+        }
+
+        mod integer_cells {
+            use super::*;
+
+            fn integer_cells_options() -> Options {
+                Options {
+                    integer_cells: true,
+                    ..Options::default()
+                }
+            }
+
+            #[test]
+            fn test_put_then_get() {
+                for &value in &[300, -5, 1 << 40, 65] {
+                    let mut interpreter =
+                        setup_interpreter_with_options("@", None, integer_cells_options());
+                    interpreter.stack.push(value);
+                    interpreter.stack.push(0);
+                    interpreter.stack.push(0);
+                    interpreter.run_other_operation('p').unwrap();
+
+                    interpreter.stack.push(0);
+                    interpreter.stack.push(0);
+                    interpreter.run_binary_operation('g').unwrap();
+                    assert_eq!(interpreter.stack.as_slice(), [value]);
+                }
+            }
+
+            #[test]
+            fn test_program() {
+                let interpreter = setup_interpreter_with_options(
+                    "\"d\"3*00p00g.@",
+                    None,
+                    integer_cells_options(),
+                );
+                assert_eq!(interpreter.output_handle, "300 ".as_bytes());
+            }
+
+            #[test]
+            fn test_disabled_by_default() {
+                let mut interpreter = setup_interpreter("@", None);
+                interpreter.stack.push(300);
+                interpreter.stack.push(0);
+                interpreter.stack.push(0);
+                assert!(interpreter.run_other_operation('p').is_err());
+            }
+        }
+
+        mod reset {
+            use super::*;
+
+            #[test]
+            fn test_self_modifying_program() {
+                // Each run replaces the 1 with a 2, so a second run without the reset
+                // would print 2
+                let mut interpreter =
+                    Interpreter::new("1.\"2\"00p@", Vec::new(), "".as_bytes(), None, None).unwrap();
+
+                interpreter.execute().unwrap();
+                assert_eq!(interpreter.playfield.code_map[0][0], '2');
+                interpreter.reset();
+                interpreter.execute().unwrap();
+
+                assert_eq!(interpreter.output_handle, "1 1 ".as_bytes());
+            }
+
+            #[test]
+            fn test_state() {
+                let options = Options {
+                    initial_stack: vec![7],
+                    seed: Some(3),
+                    ..Options::default()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    "v\n>\"a\"00p ?",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    options,
+                )
+                .unwrap();
+
+                for _ in 0..8 {
+                    interpreter.step().unwrap();
+                }
+                assert_eq!(interpreter.playfield.code_map[0][0], 'a');
+                interpreter.reset();
+
+                assert_eq!(interpreter.stack(), [7]);
+                assert_eq!(interpreter.mode(), Mode::Command);
+                assert_eq!(interpreter.steps(), 0);
+                assert_eq!(interpreter.playfield.code_map[0][0], 'v');
+                assert_eq!(
+                    interpreter.playfield.program_counter_position,
+                    Coord { x: 0, y: 0 }
+                );
+                assert_eq!(
+                    interpreter.playfield.program_counter_direction,
+                    Direction::Right
+                );
+            }
+        }
+
+        mod autoflush {
+            use super::*;
 
-            assert_eq!(
-                interpreter.playfield.program_counter_position,
-                Coord { x: 1, y: 0 }
-            );
-            assert_eq!(
-                interpreter.playfield.program_counter_direction,
-                Direction::Left
-            );
+            // Records what is written, along with how many times it was flushed
+            #[derive(Default)]
+            struct FlushCounter {
+                written: Vec<u8>,
+                flushes: usize,
+            }
 
-            interpreter.playfield.update_program_counter();
-            assert_eq!(interpreter.playfield.get_next_character(), '5');
-        }
+            impl Write for FlushCounter {
+                fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                    self.written.extend_from_slice(buf);
+                    Ok(buf.len())
+                }
 
-        #[test]
-        fn test_alternative_output_handle() {
-            let input_handle = io::stdin();
-            let mut output_handle: Vec<u8> = Vec::new();
-            let interpreter =
-                Interpreter::new("5:.,@", &mut output_handle, input_handle.lock(), None, None);
-            assert!(interpreter.is_ok());
-        }
+                fn flush(&mut self) -> io::Result<()> {
+                    self.flushes += 1;
+                    Ok(())
+                }
+            }
 
-        #[test]
-        fn test_alternative_input_handle() {
-            let mut input = "input".as_bytes();
-            let interpreter = Interpreter::new("5:.,@", io::stdout(), &mut input, None, None);
-            assert!(interpreter.is_ok());
-        }
-    }
+            fn run_with_autoflush(code: &str, autoflush: bool) -> FlushCounter {
+                let options = Options {
+                    autoflush,
+                    ..Options::default()
+                };
 
-    mod befunge_code {
-        use super::*;
+                let mut output = FlushCounter::default();
+                Interpreter::with_options(code, &mut output, "5\n".as_bytes(), None, None, options)
+                    .unwrap()
+                    .execute()
+                    .unwrap();
+                output
+            }
 
-        mod example_programs {
-            use super::*;
-            // Alternative to integration tests (temporarily)
-            // These programs are taken from https://esolangs.org/wiki/Befunge#Befunge-93_and_Befunge-98
+            #[test]
+            fn test_same_output() {
+                for &(name, code) in samples::ALL {
+                    assert_eq!(
+                        run_with_autoflush(code, false).written,
+                        run_with_autoflush(code, true).written,
+                        "{} wrote different output with autoflush",
+                        name
+                    );
+                }
+            }
 
             #[test]
-            fn test_hello_world() {
-                let interpreter = setup_interpreter("64+\"!dlroW ,olleH\">:#,_@", None);
-                assert_eq!(interpreter.output_handle, "Hello, World!\n".as_bytes());
+            fn test_flushes() {
+                // Without autoflush, the output is only flushed once the program halts
+                let output = run_with_autoflush(samples::HELLO_WORLD, false);
+                assert_eq!(output.written, "Hello, World!\n".as_bytes());
+                assert_eq!(output.flushes, 1);
+
+                let output = run_with_autoflush(samples::HELLO_WORLD, true);
+                assert_eq!(output.flushes, 15);
             }
 
             #[test]
-            fn test_factorial() {
-                let interpreter =
-                    setup_interpreter("&>:1-:v v *_$.@\n ^    _$>\\:^", Some("5".as_bytes()));
-                assert_eq!(interpreter.output_handle, "120 ".as_bytes());
+            fn test_flush_before_input() {
+                let output = run_with_autoflush("\"?\",&.@", false);
+                assert_eq!(output.written, "?5 ".as_bytes());
+                assert_eq!(output.flushes, 2);
             }
+        }
+
+        mod max_put_writes {
+            use super::*;
 
             #[test]
-            fn test_sieve_of_eratosthenes() {
-                let interpreter = setup_interpreter("2>:3g\" \"-!v\\  g30          <\n |!`\"O\":+1_:.:03p>03g+:\"O\"`|\n @               ^  p3\\\" \":<\n2 234567890123456789012345678901234567890123456789012345678901234567890123456789", None);
+            fn test_limit_exceeded() {
+                let options = Options {
+                    max_put_writes: Some(3),
+                    ..Options::default()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    "v\n>\"v\"00p",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    options,
+                )
+                .unwrap();
 
-                assert_eq!(
-                    interpreter.output_handle,
-                    "2 3 5 7 11 13 17 19 23 29 31 37 41 43 47 53 59 61 67 71 73 79 ".as_bytes()
-                );
+                let err = interpreter.execute().unwrap_err();
+                match err.downcast_ref::<BefungeError>() {
+                    Some(BefungeError::PutLimitExceeded { limit }) => assert_eq!(*limit, 3),
+                    _ => panic!("Expected the put limit to be exceeded, got {:?}", err),
+                }
+                assert_eq!(interpreter.put_writes, 4);
             }
 
             #[test]
-            fn test_quine_one() {
-                let interpreter =
-                    setup_interpreter("01->1# +# :# 0# g# ,# :# 5# 8# *# 4# +# -# _@", None);
-                assert_eq!(
-                    interpreter.output_handle,
-                    "01->1# +# :# 0# g# ,# :# 5# 8# *# 4# +# -# _@".as_bytes()
-                );
+            fn test_within_limit() {
+                let options = Options {
+                    max_put_writes: Some(1),
+                    ..Options::default()
+                };
+                let mut interpreter = Interpreter::with_options(
+                    "\"@\"50p1.",
+                    Vec::new(),
+                    "".as_bytes(),
+                    None,
+                    None,
+                    options,
+                )
+                .unwrap();
+
+                assert!(interpreter.execute().is_ok());
+            }
+        }
+
+        mod alt_halt {
+            use super::*;
+
+            fn setup_alt_halt_interpreter(code: &str) -> Interpreter<Vec<u8>, &[u8]> {
+                let options = Options {
+                    alt_halt: Some('H'),
+                    ..Options::default()
+                };
+
+                Interpreter::with_options(code, Vec::new(), "".as_bytes(), None, None, options)
+                    .unwrap()
             }
 
             #[test]
-            fn test_quine_two() {
-                let interpreter =
-                    setup_interpreter("0 v\n \"<@_ #! #: #,<*2-1*92,*84,*25,+*92*4*55.0", None);
-                assert_eq!(
-                    interpreter.output_handle,
-                    "0 v\n \"<@_ #! #: #,<*2-1*92,*84,*25,+*92*4*55.0 ".as_bytes()
-                );
+            fn test_halts_on_alt_halt() {
+                let mut interpreter = setup_alt_halt_interpreter("\"@\",H1.@");
+                assert!(interpreter.execute().is_ok());
+                assert_eq!(interpreter.output_handle, "@".as_bytes());
             }
 
-            #[should_panic]
             #[test]
-            fn test_quine_three() {
-                let interpreter = setup_interpreter(":0g,:\"~\"`#@_1+0\"Quines are Fun\">_", None);
-                assert_eq!(
-                    interpreter.output_handle,
-                    ":0g,:\"~\"`#@_1+0\"Quines are Fun\">_".as_bytes()
-                );
+            fn test_at_still_halts() {
+                let mut interpreter = setup_alt_halt_interpreter("1.@H");
+                assert!(interpreter.execute().is_ok());
+                assert_eq!(interpreter.output_handle, "1 ".as_bytes());
+            }
+
+            #[test]
+            fn test_existing_command() {
+                for &alt_halt in &['>', '5', ' ', '@'] {
+                    let options = Options {
+                        alt_halt: Some(alt_halt),
+                        ..Options::default()
+                    };
+
+                    let result = Interpreter::with_options(
+                        "1.@",
+                        Vec::new(),
+                        "".as_bytes(),
+                        None,
+                        None,
+                        options,
+                    );
+                    assert!(result.is_err());
+                }
             }
         }
 
@@ -571,7 +3451,7 @@ mod tests {
                     let mut interpreter = setup_interpreter("5@", None);
                     let result = interpreter.run_unary_operation(':');
                     assert!(result.is_ok());
-                    assert_eq!(interpreter.stack, vec![5, 5]);
+                    assert_eq!(interpreter.stack.as_slice(), [5, 5]);
                 }
 
                 #[test]
@@ -579,7 +3459,7 @@ mod tests {
                     let mut interpreter = setup_interpreter("5@", None);
                     let result = interpreter.run_unary_operation('$');
                     assert!(result.is_ok());
-                    assert_eq!(interpreter.stack, vec![]);
+                    assert!(interpreter.stack.is_empty());
                 }
 
                 #[test]
@@ -590,6 +3470,20 @@ mod tests {
                     assert_eq!(interpreter.output_handle, "5 ".as_bytes());
                 }
 
+                #[test]
+                fn test_write_integer_without_trailing_space() {
+                    let options = Options {
+                        no_trailing_space: true,
+                        ..Options::default()
+                    };
+
+                    let interpreter = setup_interpreter_with_options("5.@", None, options);
+                    assert_eq!(interpreter.output_handle, "5".as_bytes());
+
+                    let interpreter = setup_interpreter("5.@", None);
+                    assert_eq!(interpreter.output_handle, "5 ".as_bytes());
+                }
+
                 mod write_character {
                     use super::*;
 
@@ -616,6 +3510,54 @@ mod tests {
                         assert!(result.is_ok());
                         assert_eq!(interpreter.output_handle, "á".as_bytes());
                     }
+
+                    #[test]
+                    fn test_value_above_ascii() {
+                        let mut interpreter = setup_interpreter("@", None);
+                        interpreter.stack.push(200);
+                        assert!(interpreter.run_unary_operation(',').is_ok());
+                        assert_eq!(interpreter.output_handle, "\u{c8}".as_bytes());
+                    }
+
+                    #[test]
+                    fn test_strict_ascii() {
+                        let options = Options {
+                            strict_ascii: true,
+                            ..Options::default()
+                        };
+
+                        let mut interpreter = setup_interpreter_with_options("@", None, options);
+                        interpreter.stack.push(200);
+                        match interpreter.run_unary_operation(',') {
+                            Err(err) => match err.downcast_ref::<BefungeError>() {
+                                Some(BefungeError::NonAsciiValue(value)) => assert_eq!(*value, 200),
+                                _ => panic!("Expected a non-ASCII value, got {:?}", err),
+                            },
+                            Ok(()) => panic!("Expected 200 to be rejected"),
+                        }
+                        interpreter.stack.push(127);
+                        assert!(interpreter.run_unary_operation(',').is_ok());
+                        assert_eq!(interpreter.output_handle, "\u{7f}".as_bytes());
+                    }
+
+                    #[test]
+                    fn test_ascii_cell_range() {
+                        let options = Options {
+                            cell_range: CellRange::Ascii,
+                            ..Options::default()
+                        };
+
+                        let mut interpreter = setup_interpreter_with_options("@", None, options);
+                        interpreter.stack.push(200);
+                        match interpreter.run_unary_operation(',') {
+                            Err(err) => match err.downcast_ref::<BefungeError>() {
+                                Some(BefungeError::NonAsciiValue(value)) => assert_eq!(*value, 200),
+                                _ => panic!("Expected a non-ASCII value, got {:?}", err),
+                            },
+                            Ok(()) => panic!("Expected 200 to be rejected"),
+                        }
+                        assert!(interpreter.output_handle.is_empty());
+                    }
                 }
             }
 
@@ -703,6 +3645,53 @@ mod tests {
                         let result = interpreter.run_binary_operation('/');
                         assert!(result.is_err());
                     }
+
+                    #[test]
+                    fn test_truncated_negative() {
+                        let mut interpreter = setup_interpreter("07-2@", None);
+                        interpreter.run_binary_operation('/').unwrap();
+                        assert_eq!(interpreter.stack.last().unwrap(), &-3);
+                    }
+
+                    #[test]
+                    fn test_floored_negative() {
+                        let options = Options {
+                            division: DivisionMode::Floored,
+                            ..Options::default()
+                        };
+                        let mut interpreter =
+                            setup_interpreter_with_options("07-2@", None, options);
+                        interpreter.run_binary_operation('/').unwrap();
+                        assert_eq!(interpreter.stack.last().unwrap(), &-4);
+                    }
+
+                    #[test]
+                    fn test_floored_exact() {
+                        let options = Options {
+                            division: DivisionMode::Floored,
+                            ..Options::default()
+                        };
+                        let mut interpreter =
+                            setup_interpreter_with_options("08-2@", None, options);
+                        interpreter.run_binary_operation('/').unwrap();
+                        assert_eq!(interpreter.stack.last().unwrap(), &-4);
+                    }
+
+                    #[test]
+                    fn test_overflow() {
+                        for &division in &[DivisionMode::Truncated, DivisionMode::Floored] {
+                            let options = Options {
+                                division,
+                                ..Options::default()
+                            };
+                            let mut interpreter =
+                                setup_interpreter_with_options("@", None, options);
+                            interpreter.stack.push(i64::MIN);
+                            interpreter.stack.push(-1);
+                            interpreter.run_binary_operation('/').unwrap();
+                            assert_eq!(interpreter.stack.as_slice(), [i64::MIN]);
+                        }
+                    }
                 }
 
                 mod modulo {
@@ -722,6 +3711,76 @@ mod tests {
                         let result = interpreter.run_binary_operation('%');
                         assert!(result.is_err());
                     }
+
+                    #[test]
+                    fn test_truncated_negative() {
+                        let mut interpreter = setup_interpreter("07-2@", None);
+                        interpreter.run_binary_operation('%').unwrap();
+                        assert_eq!(interpreter.stack.last().unwrap(), &-1);
+                    }
+
+                    #[test]
+                    fn test_floored_negative() {
+                        let options = Options {
+                            division: DivisionMode::Floored,
+                            ..Options::default()
+                        };
+                        let mut interpreter =
+                            setup_interpreter_with_options("07-2@", None, options);
+                        interpreter.run_binary_operation('%').unwrap();
+                        assert_eq!(interpreter.stack.last().unwrap(), &1);
+                    }
+
+                    #[test]
+                    fn test_overflow() {
+                        for &division in &[DivisionMode::Truncated, DivisionMode::Floored] {
+                            let options = Options {
+                                division,
+                                ..Options::default()
+                            };
+                            let mut interpreter =
+                                setup_interpreter_with_options("@", None, options);
+                            interpreter.stack.push(i64::MIN);
+                            interpreter.stack.push(-1);
+                            interpreter.run_binary_operation('%').unwrap();
+                            assert_eq!(interpreter.stack.as_slice(), [0]);
+                        }
+                    }
+
+                    #[test]
+                    fn test_matches_division() {
+                        let operands = [(-7, 2), (7, -2), (-7, -2), (7, 2), (-8, 2), (-1, 5)];
+
+                        for &division in &[DivisionMode::Truncated, DivisionMode::Floored] {
+                            for &(b, a) in &operands {
+                                let options = Options {
+                                    division,
+                                    ..Options::default()
+                                };
+                                let mut interpreter =
+                                    setup_interpreter_with_options("@", None, options);
+
+                                interpreter.stack.push(b);
+                                interpreter.stack.push(a);
+                                interpreter.run_binary_operation('/').unwrap();
+                                let quotient = interpreter.stack.pop();
+
+                                interpreter.stack.push(b);
+                                interpreter.stack.push(a);
+                                interpreter.run_binary_operation('%').unwrap();
+                                let remainder = interpreter.stack.pop();
+
+                                assert_eq!(
+                                    quotient * a + remainder,
+                                    b,
+                                    "{} / {} with {:?}",
+                                    b,
+                                    a,
+                                    division
+                                );
+                            }
+                        }
+                    }
                 }
 
                 mod greater_than {
@@ -750,6 +3809,40 @@ mod tests {
                         assert!(result.is_ok());
                         assert_eq!(interpreter.stack.last().unwrap(), &0);
                     }
+
+                    #[test]
+                    fn test_byte_comparison_of_byte_values() {
+                        let options = Options {
+                            comparison: ComparisonMode::Byte,
+                            ..Options::default()
+                        };
+
+                        // 200 and 100 fit in a byte, so both modes agree
+                        let mut signed = setup_interpreter("55*8*55*4*@", None);
+                        let mut byte = setup_interpreter_with_options("55*8*55*4*@", None, options);
+                        signed.run_binary_operation('`').unwrap();
+                        byte.run_binary_operation('`').unwrap();
+
+                        assert_eq!(signed.stack.last().unwrap(), &1);
+                        assert_eq!(byte.stack.last().unwrap(), &1);
+                    }
+
+                    #[test]
+                    fn test_byte_comparison_of_negative_values() {
+                        let options = Options {
+                            comparison: ComparisonMode::Byte,
+                            ..Options::default()
+                        };
+
+                        // -1 is masked to 255 when comparing bytes
+                        let mut signed = setup_interpreter("01-1@", None);
+                        let mut byte = setup_interpreter_with_options("01-1@", None, options);
+                        signed.run_binary_operation('`').unwrap();
+                        byte.run_binary_operation('`').unwrap();
+
+                        assert_eq!(signed.stack.last().unwrap(), &0);
+                        assert_eq!(byte.stack.last().unwrap(), &1);
+                    }
                 }
 
                 #[test]
@@ -757,7 +3850,7 @@ mod tests {
                     let mut interpreter = setup_interpreter("65@", None);
                     let result = interpreter.run_binary_operation('\\');
                     assert!(result.is_ok());
-                    assert_eq!(interpreter.stack, vec![5, 6]);
+                    assert_eq!(interpreter.stack.as_slice(), [5, 6]);
                 }
 
                 mod get {
@@ -777,6 +3870,37 @@ mod tests {
                         let result = interpreter.run_binary_operation('g');
                         assert!(result.is_err());
                     }
+
+                    #[test]
+                    fn test_swapped_coords() {
+                        let options = Options {
+                            swap_pg_coords: true,
+                            ..Options::default()
+                        };
+
+                        let interpreter = setup_interpreter("10g.@\n5", None);
+                        assert_eq!(interpreter.output_handle, "48 ".as_bytes());
+
+                        let interpreter = setup_interpreter_with_options("10g.@\n5", None, options);
+                        assert_eq!(interpreter.output_handle, "53 ".as_bytes());
+                    }
+
+                    #[test]
+                    fn test_pop_order() {
+                        // y is popped first, so the x pushed below it is 2 and y is 0
+                        let mut interpreter = setup_interpreter("@ab", None);
+                        interpreter.stack.push(2);
+                        interpreter.stack.push(0);
+                        interpreter.run_binary_operation('g').unwrap();
+                        assert_eq!(interpreter.stack.as_slice(), ['b' as i64]);
+                    }
+
+                    #[test]
+                    fn test_put_then_get() {
+                        let interpreter = setup_interpreter("\"X\"31p31g.@\n    ", None);
+                        assert_eq!(interpreter.output_handle, "88 ".as_bytes());
+                        assert_eq!(interpreter.playfield.code_map[1][3], 'X');
+                    }
                 }
             }
 
@@ -850,6 +3974,14 @@ mod tests {
                     assert_eq!(interpreter.mode, Mode::String);
                 }
 
+                #[test]
+                fn test_comment_mode_change() {
+                    let mut interpreter = setup_interpreter("5@", None);
+                    let result = interpreter.run_other_operation(';');
+                    assert!(result.is_ok());
+                    assert_eq!(interpreter.mode, Mode::Comment);
+                }
+
                 #[test]
                 fn test_bridge_mode_change() {
                     let mut interpreter = setup_interpreter("5@", None);
@@ -875,6 +4007,45 @@ mod tests {
                         let result = interpreter.run_other_operation('p');
                         assert!(result.is_err());
                     }
+
+                    #[test]
+                    fn test_swapped_coords() {
+                        let options = Options {
+                            swap_pg_coords: true,
+                            ..Options::default()
+                        };
+
+                        let interpreter =
+                            setup_interpreter_with_options("\"X\"10p@\n5", None, options);
+                        assert_eq!(interpreter.playfield.code_map[1][0], 'X');
+                    }
+
+                    #[test]
+                    fn test_pop_order() {
+                        // y is popped first, then x, then the value to store
+                        let mut interpreter = setup_interpreter("@ab", None);
+                        interpreter.stack.push('Z' as i64);
+                        interpreter.stack.push(2);
+                        interpreter.stack.push(0);
+                        interpreter.run_other_operation('p').unwrap();
+                        assert_eq!(interpreter.playfield.code_map[0], ['@', 'a', 'Z']);
+                        assert!(interpreter.stack.is_empty());
+                    }
+
+                    #[test]
+                    fn test_ascii_cell_range() {
+                        let options = Options {
+                            cell_range: CellRange::Ascii,
+                            ..Options::default()
+                        };
+
+                        let mut interpreter = setup_interpreter_with_options("@", None, options);
+                        interpreter.stack.push(200);
+                        interpreter.stack.push(0);
+                        interpreter.stack.push(0);
+                        assert!(interpreter.run_other_operation('p').is_err());
+                        assert_eq!(interpreter.playfield.code_map[0], ['@']);
+                    }
                 }
 
                 #[test]
@@ -886,47 +4057,195 @@ mod tests {
                 }
 
                 #[test]
-                fn test_read_character() {
+                fn test_read_integers_from_one_line() {
+                    let mut interpreter = setup_interpreter("@", Some("3 4  5\n6\n".as_bytes()));
+                    for _ in 0..5 {
+                        interpreter.run_other_operation('&').unwrap();
+                    }
+                    assert_eq!(interpreter.stack.as_slice(), [3, 4, 5, 6, -1]);
+                }
+
+                #[test]
+                fn test_read_integers_program() {
+                    let interpreter = setup_interpreter("&&&++.@", Some("3 4 5\n".as_bytes()));
+                    assert_eq!(interpreter.output_handle, "12 ".as_bytes());
+                }
+
+                #[test]
+                fn test_read_character_after_integers() {
+                    let mut interpreter = setup_interpreter("@", Some("3 x\ny\n".as_bytes()));
+                    interpreter.run_other_operation('&').unwrap();
+                    interpreter.run_other_operation('~').unwrap();
+                    interpreter.run_other_operation('~').unwrap();
+                    assert_eq!(interpreter.stack.as_slice(), [3, 120, 121]);
+                }
+
+                #[test]
+                fn test_invalid_integer_token() {
+                    let mut interpreter = setup_interpreter("@", Some("3 x\n".as_bytes()));
+                    interpreter.run_other_operation('&').unwrap();
+                    assert!(interpreter.run_other_operation('&').is_err());
+                }
+
+                #[test]
+                fn test_read_integer_at_end_of_input() {
                     let mut interpreter = setup_interpreter("@", Some("5".as_bytes()));
-                    let result = interpreter.run_other_operation('~');
-                    assert!(result.is_ok());
-                    assert_eq!(interpreter.stack.last().unwrap(), &53);
+                    interpreter.run_other_operation('&').unwrap();
+                    interpreter.run_other_operation('&').unwrap();
+                    assert_eq!(interpreter.stack.as_slice(), [5, -1]);
                 }
-            }
-        }
-    }
 
-    mod convert_int_to_char {
-        use super::*;
+                #[test]
+                fn test_read_integer_with_separators() {
+                    let options = Options {
+                        int_separators: vec![',', ' '],
+                        ..Options::default()
+                    };
+                    let mut interpreter = setup_interpreter_with_options(
+                        "@",
+                        Some("1,000\n2 500".as_bytes()),
+                        options,
+                    );
 
-        #[test]
-        fn test_basic() {
-            assert_eq!(convert_int_to_char(57).unwrap(), '9');
-            assert_eq!(convert_int_to_char(38).unwrap(), '&');
-            assert_eq!(convert_int_to_char(76).unwrap(), 'L');
-            assert_eq!(convert_int_to_char(103).unwrap(), 'g');
-        }
+                    interpreter.run_other_operation('&').unwrap();
+                    assert_eq!(interpreter.stack.last().unwrap(), &1000);
+                    interpreter.run_other_operation('&').unwrap();
+                    assert_eq!(interpreter.stack.last().unwrap(), &2500);
+                }
 
-        #[test]
-        fn test_extended_character_set() {
-            assert_eq!(convert_int_to_char(233).unwrap(), 'é');
-            assert_eq!(convert_int_to_char(247).unwrap(), '÷');
-        }
+                #[test]
+                fn test_read_integer_with_separators_by_default() {
+                    let mut interpreter = setup_interpreter("@", Some("1,000".as_bytes()));
+                    assert!(interpreter.run_other_operation('&').is_err());
+                }
 
-        #[test]
-        fn test_upper_bound() {
-            assert_eq!(convert_int_to_char(255).unwrap(), 'ÿ');
-        }
+                mod read_character {
+                    use super::*;
 
-        #[test]
-        fn test_lower_bound() {
-            assert_eq!(convert_int_to_char(0).unwrap(), 0_u8 as char);
-        }
+                    fn setup_char_input_interpreter(
+                        input: &str,
+                        char_input: CharInputMode,
+                    ) -> Interpreter<Vec<u8>, &[u8]> {
+                        let options = Options {
+                            char_input,
+                            ..Options::default()
+                        };
 
-        #[test]
-        fn test_out_of_bounds() {
-            assert!(convert_int_to_char(5555).is_err());
-            assert!(convert_int_to_char(-333).is_err());
+                        setup_interpreter_with_options("@", Some(input.as_bytes()), options)
+                    }
+
+                    #[test]
+                    fn test_basic() {
+                        let mut interpreter = setup_interpreter("@", Some("5".as_bytes()));
+                        let result = interpreter.run_other_operation('~');
+                        assert!(result.is_ok());
+                        assert_eq!(interpreter.stack.last().unwrap(), &53);
+                    }
+
+                    #[test]
+                    fn test_end_of_input() {
+                        for &char_input in &[
+                            CharInputMode::Unicode,
+                            CharInputMode::Ascii,
+                            CharInputMode::Byte,
+                            CharInputMode::SingleByte,
+                        ] {
+                            let mut interpreter = setup_char_input_interpreter("", char_input);
+                            interpreter.run_other_operation('~').unwrap();
+                            assert_eq!(interpreter.stack.as_slice(), [-1]);
+                        }
+                    }
+
+                    #[test]
+                    fn test_unicode_mode() {
+                        let mut interpreter =
+                            setup_char_input_interpreter("a", CharInputMode::Unicode);
+                        interpreter.run_other_operation('~').unwrap();
+                        assert_eq!(interpreter.stack.last().unwrap(), &97);
+
+                        let mut interpreter =
+                            setup_char_input_interpreter("\u{e1}", CharInputMode::Unicode);
+                        interpreter.run_other_operation('~').unwrap();
+                        assert_eq!(interpreter.stack.last().unwrap(), &0xe1);
+                    }
+
+                    #[test]
+                    fn test_ascii_mode() {
+                        let mut interpreter =
+                            setup_char_input_interpreter("a", CharInputMode::Ascii);
+                        interpreter.run_other_operation('~').unwrap();
+                        assert_eq!(interpreter.stack.last().unwrap(), &97);
+
+                        let mut interpreter =
+                            setup_char_input_interpreter("\u{e1}", CharInputMode::Ascii);
+                        assert!(interpreter.run_other_operation('~').is_err());
+                    }
+
+                    #[test]
+                    fn test_byte_mode() {
+                        let mut interpreter =
+                            setup_char_input_interpreter("a", CharInputMode::Byte);
+                        interpreter.run_other_operation('~').unwrap();
+                        assert_eq!(interpreter.stack.last().unwrap(), &97);
+
+                        // The two bytes of the UTF-8 encoding are read one at a time
+                        let mut interpreter =
+                            setup_char_input_interpreter("\u{e1}", CharInputMode::Byte);
+                        interpreter.run_other_operation('~').unwrap();
+                        interpreter.run_other_operation('~').unwrap();
+                        assert_eq!(interpreter.stack.as_slice(), [0xc3, 0xa1]);
+                    }
+
+                    #[test]
+                    fn test_single_byte_mode() {
+                        let options = Options {
+                            char_input: CharInputMode::SingleByte,
+                            ..Options::default()
+                        };
+
+                        // A raw byte is read even though it is not valid UTF-8
+                        let mut interpreter =
+                            setup_interpreter_with_options("@", Some(b"\xe9\n"), options);
+                        interpreter.run_other_operation('~').unwrap();
+                        assert_eq!(interpreter.stack.as_slice(), [0xe9]);
+
+                        let mut interpreter =
+                            setup_char_input_interpreter("\u{e1}", CharInputMode::SingleByte);
+                        assert!(interpreter.run_other_operation('~').is_err());
+                    }
+
+                    #[test]
+                    fn test_single_byte_mode_after_integer() {
+                        // The rest of the line after & must be a single byte as well
+                        let mut interpreter =
+                            setup_char_input_interpreter("1 a", CharInputMode::SingleByte);
+                        interpreter.run_other_operation('&').unwrap();
+                        interpreter.run_other_operation('~').unwrap();
+                        assert_eq!(interpreter.stack.as_slice(), [1, 97]);
+
+                        let mut interpreter =
+                            setup_char_input_interpreter("1 \u{e1}", CharInputMode::SingleByte);
+                        interpreter.run_other_operation('&').unwrap();
+                        assert!(interpreter.run_other_operation('~').is_err());
+                    }
+
+                    #[test]
+                    fn test_invalid_utf8() {
+                        let mut interpreter = setup_interpreter_with_options(
+                            "@",
+                            Some(b"\xe9\n"),
+                            Options::default(),
+                        );
+                        let err = interpreter.run_other_operation('~').unwrap_err();
+                        match err.downcast_ref::<BefungeError>() {
+                            Some(BefungeError::InvalidInput { expected, .. }) => {
+                                assert_eq!(*expected, "UTF-8 line")
+                            }
+                            _ => panic!("Expected invalid input, got {:?}", err),
+                        }
+                    }
+                }
+            }
         }
     }
 }
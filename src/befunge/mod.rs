@@ -14,10 +14,25 @@
  * limitations under the License.
  */
 
+mod builder;
+mod cell;
+mod command;
 mod error;
 mod interpreter;
+mod options;
 mod playfield;
+pub mod samples;
+mod stack;
 
+pub use self::builder::InterpreterBuilder;
+pub use self::cell::Cell;
+pub use self::command::{command_category, CommandCategory};
 pub use self::error::Error;
-pub use self::interpreter::Interpreter;
-pub use self::playfield::{Coord, Direction};
+pub use self::interpreter::{
+    CommandHandler, Interpreter, Mode, RunStats, StackObserver, StepResult,
+};
+pub use self::options::{
+    CellRange, CharInputMode, ComparisonMode, CrlfMode, DivisionMode, Options, Padding,
+};
+pub use self::playfield::{Coord, Direction, GridFormat, Playfield};
+pub use self::stack::Stack;
@@ -0,0 +1,180 @@
+/* befunge/options.rs - Contains the configurable options of the Befunge-93 interpreter
+ * Copyright 2018 Arnav Borborah
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// How the greater than (`) command compares its operands
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ComparisonMode {
+    // Compare the operands as signed 64-bit integers
+    #[default]
+    Signed,
+    // Compare only the lowest byte of each operand as an unsigned value (0-255),
+    // like interpreters that store values in byte cells
+    Byte,
+}
+
+// How the integer division (/) command rounds a quotient that is not a whole number.
+// The modulo (%) command always uses the same rounding, so (a / b) * b + a % b == a.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DivisionMode {
+    // Round toward zero, so -7 / 2 is -3 and -7 % 2 is -1
+    #[default]
+    Truncated,
+    // Round toward negative infinity, so -7 / 2 is -4 and -7 % 2 is 1
+    Floored,
+}
+
+// The values the output character (,) and put (p) commands accept
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CellRange {
+    // Values between 0 and 255, where values above 127 are Latin-1 characters
+    #[default]
+    Byte,
+    // Only ASCII values, between 0 and 127
+    Ascii,
+}
+
+// What short lines of the program are padded with
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Padding {
+    // Pad with spaces (32), as in standard Befunge-93
+    #[default]
+    Space,
+    // Pad with null characters (0), like interpreters that zero-initialize the grid.
+    // Null cells are executed as no-ops, like spaces.
+    Null,
+}
+
+// How the input character (~) command reads its input
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CharInputMode {
+    // Read a line and push the single Unicode character on it
+    #[default]
+    Unicode,
+    // Read a line and push the single character on it, which must be ASCII
+    Ascii,
+    // Read exactly one byte of input and push its value (0-255), so a multibyte
+    // UTF-8 character is read one byte at a time
+    Byte,
+    // Read a line and push the single byte on it (0-255), so a raw byte that is not
+    // valid UTF-8 can be read, but a multibyte UTF-8 character is an error
+    SingleByte,
+}
+
+// How a carriage return (13) written right before a line feed (10) is output
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CrlfMode {
+    // Write both bytes as they are
+    #[default]
+    PassThrough,
+    // Write only the line feed, so "\r\n" becomes "\n". Carriage returns that are
+    // not followed by a line feed are still written.
+    Coalesce,
+}
+
+// Options that change how the interpreter behaves. The default options
+// match standard Befunge-93.
+#[derive(Debug, Default)]
+pub struct Options {
+    // Number of values to reserve space for on the stack before execution starts
+    pub stack_capacity: usize,
+    // Values pushed onto the stack before execution starts, in order, so the last
+    // value is on top of the stack
+    pub initial_stack: Vec<i64>,
+    // How the greater than (`) command compares its operands
+    pub comparison: ComparisonMode,
+    // How the integer division (/) and modulo (%) commands round their results
+    pub division: DivisionMode,
+    // If set, stepping once this many steps have been executed is an error, which
+    // stops programs that never reach @
+    pub max_steps: Option<u64>,
+    // If set, running more than this many consecutive spaces in command mode is
+    // an error, since it usually means the program counter has wandered off into
+    // an empty part of the grid and will never reach @
+    pub max_consecutive_spaces: Option<u64>,
+    // If set, executing put (p) more than this many times is an error, which bounds
+    // how much a program can modify itself
+    pub max_put_writes: Option<u64>,
+    // Whether g and p pop x before y, the opposite of the spec, to match
+    // interpreters that use that order
+    pub swap_pg_coords: bool,
+    // What short lines of the program are padded with
+    pub padding: Padding,
+    // The values , and p accept, which are checked by `Cell::try_from_range`
+    pub cell_range: CellRange,
+    // How the input character (~) command reads its input
+    pub char_input: CharInputMode,
+    // Whether the output integer (.) command writes just the number, without the
+    // space that normally follows it
+    pub no_trailing_space: bool,
+    // Whether the output character (,) command only accepts ASCII values (0-127),
+    // returning `BefungeError::NonAsciiValue` otherwise. This is `CellRange::Ascii`
+    // for , alone, so p still accepts any value in `cell_range`.
+    pub strict_ascii: bool,
+    // Characters the input integer (&) command removes from its input before parsing
+    // it, such as the thousands separator in "1,000"
+    pub int_separators: Vec<char>,
+    // If set, source lines starting with this character are removed before the
+    // playfield is built. Since later lines move up, this changes the coordinates
+    // of everything below a removed line.
+    pub comment_char: Option<char>,
+    // If set, lines of the form `;label NAME` are removed before the playfield is
+    // built, and execution starts at the label with this name instead of the passed
+    // position. A label marks the cell right below its `;`. Labels are found after
+    // comment lines are removed, so with `;` as the comment character they must be
+    // indented.
+    pub entry_label: Option<String>,
+    // How a carriage return written right before a line feed is output
+    pub crlf: CrlfMode,
+    // If set, the program halts once it has written this many newlines, like
+    // piping its output through `head`. Output after the last newline is dropped.
+    pub max_output_lines: Option<u64>,
+    // Whether & and ~ read input provided with `Interpreter::provide_input` instead
+    // of the input handle. When not enough input has been provided, stepping returns
+    // `StepResult::NeedsInput` rather than blocking.
+    pub programmatic_input: bool,
+    // Whether to flush the output handle after every write, so output shows up right
+    // away. Otherwise it is only flushed when a step halts, needs input or errors,
+    // and before & or ~ block on the input handle.
+    pub autoflush: bool,
+    // Whether to record every byte of output along with the step that wrote it
+    pub record_output_events: bool,
+    // Seed for the random number generator used by ?. Runs with the same seed
+    // make the same random choices. If not set, a random seed is used.
+    pub seed: Option<u64>,
+    // Whether to enable commands outside of the Befunge-93 specification:
+    //
+    // A - Pops expected and then actual, and errors unless they are equal
+    pub extended_commands: bool,
+    // Whether ; starts a comment like in Befunge-98. Every cell the program counter
+    // reaches up to and including the next ; is skipped without being executed.
+    pub funge98_comments: bool,
+    // Whether ' fetches a character like in Befunge-98, pushing the value of the
+    // next cell in the current direction and then skipping over it
+    pub funge98_fetch: bool,
+    // Whether k iterates like in Befunge-98, popping n and executing the next cell
+    // in the current direction n times. The cell is skipped afterwards, so with n
+    // of 0 it is not executed at all.
+    pub funge98_iterate: bool,
+    // Whether cells can hold any integer, as in Befunge-98, so p stores the popped
+    // value unchanged and g pushes it back. Otherwise p only stores values 0-255.
+    pub integer_cells: bool,
+    // If set, this character halts the program like @ does, which @ keeps doing.
+    // Self-modifying programs can use it to avoid halting when the program counter
+    // runs over an @ that was stored as data. Any cell holding this character also
+    // halts the program, though, so it should be one the program never stores.
+    // Like with `Interpreter::register_command`, it cannot be a Befunge-93 command.
+    pub alt_halt: Option<char>,
+}
@@ -14,9 +14,17 @@
  * limitations under the License.
  */
 
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem;
+
+#[cfg(feature = "json")]
+use serde::Serialize;
+
 use super::error::Error as BefungeError;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Direction {
     Up,
     Down,
@@ -24,18 +32,43 @@ pub enum Direction {
     Right,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize))]
 pub struct Coord {
     pub x: i64,
     pub y: i64,
 }
 
+// Formats in which the whole playfield can be rendered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridFormat {
+    // Each row on its own line, exactly as stored
+    Plain,
+    // Rows framed by a border, with column and row numbers in the margins
+    Ruled,
+    // A JSON object with the dimensions and an array of row strings
+    #[cfg(feature = "json")]
+    Json,
+}
+
+// The object `GridFormat::Json` renders
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct JsonGrid<'a> {
+    width: i64,
+    height: i64,
+    rows: &'a [String],
+}
+
 // Represents the Befunge-93 playfield
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Playfield {
     // Needed so that Intepreter unit tests can check if
     // proper initialization takes place
     pub(super) code_map: Vec<Vec<char>>,
+    // Values stored with `set_value_at` that are not characters, such as negative
+    // numbers. Their cells hold U+FFFD in `code_map`.
+    wide_values: HashMap<Coord, i64>,
     pub dimensions: Coord,
 
     pub program_counter_position: Coord,
@@ -50,38 +83,99 @@ impl Playfield {
         program_counter_position: Coord,
         program_counter_direction: Direction,
     ) -> Result<Playfield, BefungeError> {
+        Playfield::with_padding(
+            code,
+            program_counter_position,
+            program_counter_direction,
+            ' ',
+        )
+    }
+
+    // Same as `Playfield::new`, but pads short lines with the passed character
+    // instead of spaces
+    pub fn with_padding(
+        code: &str,
+        program_counter_position: Coord,
+        program_counter_direction: Direction,
+        padding: char,
+    ) -> Result<Playfield, BefungeError> {
+        Playfield::from_lines(
+            code.lines(),
+            program_counter_position,
+            program_counter_direction,
+            padding,
+        )
+    }
+
+    // Same as `Playfield::new`, but takes the program code already split into rows,
+    // which should not contain newlines
+    pub fn from_rows(
+        rows: Vec<String>,
+        program_counter_position: Coord,
+        program_counter_direction: Direction,
+    ) -> Result<Playfield, BefungeError> {
+        Playfield::from_lines(
+            rows.iter().map(|row| row.as_str()),
+            program_counter_position,
+            program_counter_direction,
+            ' ',
+        )
+    }
+
+    // Builds the playfield from the lines of the program code, right-padding each
+    // line with the padding character
+    fn from_lines<'a, Lines>(
+        lines: Lines,
+        program_counter_position: Coord,
+        program_counter_direction: Direction,
+        padding: char,
+    ) -> Result<Playfield, BefungeError>
+    where
+        Lines: Iterator<Item = &'a str> + Clone,
+    {
         // Get the longest line width as the width of the playfield
-        let width = code
-            .lines()
+        let width = lines
+            .clone()
             .max_by_key(|line| line.len())
             .unwrap_or("")
             .len();
 
-        // Create a vector of vector of chars. Each line is right-padded with spaces
-        // to the longest line width.
-        let code_map = code
-            .lines()
-            .map(|line| {
-                format!("{:<width$}", line, width = width)
-                    .chars()
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<Vec<_>>>();
+        // Create a vector of vector of chars. Each line is right-padded with the
+        // padding character to the longest line width. Code with only blank lines
+        // has no cells, so it is treated like empty code rather than as rows of
+        // width 0.
+        let code_map = if width == 0 {
+            Vec::new()
+        } else {
+            lines
+                .map(|line| {
+                    let mut row = line.chars().collect::<Vec<_>>();
+                    row.resize(width, padding);
+                    row
+                })
+                .collect::<Vec<Vec<_>>>()
+        };
 
         let width = width as i64;
         let height = code_map.len() as i64;
 
-        if (program_counter_position.x > width || program_counter_position.y > height)
-            || (program_counter_position.x < 0 || program_counter_position.y < 0)
-        {
-            return Err(BefungeError(format!(
-                "Initial program counter position ({}, {}) is out of bounds!",
-                program_counter_position.x, program_counter_position.y
-            )));
+        // An empty playfield has no cells, so the origin is the only position allowed
+        let in_bounds = if code_map.is_empty() {
+            program_counter_position == Coord { x: 0, y: 0 }
+        } else {
+            (0..width).contains(&program_counter_position.x)
+                && (0..height).contains(&program_counter_position.y)
+        };
+
+        if !in_bounds {
+            return Err(BefungeError::OutOfBounds {
+                coord: program_counter_position,
+            });
         }
 
         Ok(Playfield {
             code_map,
+            wide_values: HashMap::new(),
             dimensions: Coord {
                 x: width,
                 y: height,
@@ -91,49 +185,447 @@ impl Playfield {
         })
     }
 
+    // Returns the number of " characters in each row. An odd total suggests a
+    // string that is never closed, though only the executed path really matters.
+    pub fn quote_counts(&self) -> Vec<usize> {
+        self.code_map
+            .iter()
+            .map(|row| row.iter().filter(|&&cell| cell == '"').count())
+            .collect()
+    }
+
+    // Returns an estimate of the bytes used by the grid, counting the allocated
+    // cells of each row along with the row vectors themselves
+    pub fn memory_footprint(&self) -> usize {
+        let row_size = mem::size_of::<Vec<char>>();
+        let cells = self
+            .code_map
+            .iter()
+            .map(|row| row.capacity() * mem::size_of::<char>())
+            .sum::<usize>();
+
+        mem::size_of::<Vec<Vec<char>>>() + self.code_map.capacity() * row_size + cells
+    }
+
+    // Returns every cell along with its position, row by row from the top, with each
+    // row from left to right
+    pub fn iter_cells(&self) -> impl Iterator<Item = (Coord, char)> + '_ {
+        self.code_map.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, &cell)| {
+                (
+                    Coord {
+                        x: x as i64,
+                        y: y as i64,
+                    },
+                    cell,
+                )
+            })
+        })
+    }
+
+    // Returns whether the passed position is inside the playfield
+    pub fn contains(&self, position: &Coord) -> bool {
+        (0..self.dimensions.x).contains(&position.x) && (0..self.dimensions.y).contains(&position.y)
+    }
+
     // Returns the character at the current program counter position
     pub fn get_next_character(&self) -> char {
         self.code_map[self.program_counter_position.y as usize]
             [self.program_counter_position.x as usize]
     }
 
+    // Returns the character the program counter would reach by moving once in its
+    // direction, without moving it
+    pub fn peek_next_character(&self) -> char {
+        let position = self.program_counter_position;
+        let (x, y) = self.step_from(
+            (position.x, position.y),
+            direction_index(&self.program_counter_direction),
+        );
+
+        self.code_map[y as usize][x as usize]
+    }
+
     // Modifies the playfield at a specific position. This is needed for put (p)
     // calls.
     // If the passed position is out of bounds, a BefungeError will be returned.
     pub fn set_character_at(&mut self, position: &Coord, value: char) -> Result<(), BefungeError> {
-        if (position.x < 0 || position.y < 0)
-            || (position.x > self.dimensions.x || position.y > self.dimensions.y)
-        {
-            Err(BefungeError(format!(
-                "Location ({}, {}) is out of bounds!",
-                position.x, position.y
-            )))
+        if !self.contains(position) {
+            Err(BefungeError::OutOfBounds { coord: *position })
         } else {
             self.code_map[position.y as usize][position.x as usize] = value;
+            self.wide_values.remove(position);
             Ok(())
         }
     }
 
+    // Gets the value of the cell at a specific position, which is the character
+    // there unless `set_value_at` stored a value that is not a character.
+    // If the passed position is out of bounds, a BefungeError will be returned.
+    pub fn get_value_at(&self, position: &Coord) -> Result<i64, BefungeError> {
+        let character = self.get_character_at(position)?;
+        Ok(self
+            .wide_values
+            .get(position)
+            .cloned()
+            .unwrap_or(character as i64))
+    }
+
+    // Stores any integer in the cell at a specific position, like put (p) in
+    // Befunge-98. A value that is not a character reads back as U+FFFD from
+    // `get_character_at` and `get_next_character`, which is not a command.
+    // If the passed position is out of bounds, a BefungeError will be returned.
+    pub fn set_value_at(&mut self, position: &Coord, value: i64) -> Result<(), BefungeError> {
+        match u32::try_from(value).ok().and_then(char::from_u32) {
+            Some(character) => self.set_character_at(position, character),
+            None => {
+                self.set_character_at(position, char::REPLACEMENT_CHARACTER)?;
+                self.wide_values.insert(*position, value);
+                Ok(())
+            }
+        }
+    }
+
     // Gets the character on the playfield at a specific position.
     // This is needed for get (g) calls.
     // If the passed position is out of bounds, a BefungeError will be returned.
     pub fn get_character_at(&self, position: &Coord) -> Result<char, BefungeError> {
-        if (position.x < 0 || position.y < 0)
-            || (position.x > self.dimensions.x || position.y > self.dimensions.y)
-        {
-            Err(BefungeError(format!(
-                "Location ({}, {}) is out of bounds!",
-                position.x, position.y
-            )))
+        if !self.contains(position) {
+            Err(BefungeError::OutOfBounds { coord: *position })
         } else {
             Ok(self.code_map[position.y as usize][position.x as usize])
         }
     }
 
+    // Resizes the playfield to the passed dimensions. Cells in both the old and the
+    // new playfield are preserved, new cells are set to `fill`, and cells outside
+    // the new dimensions are removed.
+    // If the program counter would be outside the new dimensions, a BefungeError
+    // will be returned and the playfield is left unchanged.
+    pub fn resize(
+        &mut self,
+        new_width: i64,
+        new_height: i64,
+        fill: char,
+    ) -> Result<(), BefungeError> {
+        let position = self.program_counter_position;
+        if !((0..new_width).contains(&position.x) && (0..new_height).contains(&position.y)) {
+            return Err(BefungeError::OutOfBounds { coord: position });
+        }
+
+        self.code_map.resize(new_height as usize, Vec::new());
+        for row in &mut self.code_map {
+            row.resize(new_width as usize, fill);
+        }
+        self.wide_values
+            .retain(|position, _| position.x < new_width && position.y < new_height);
+        self.dimensions = Coord {
+            x: new_width,
+            y: new_height,
+        };
+
+        Ok(())
+    }
+
+    // Gets the characters in the region of the playfield with the passed top left
+    // corner and size, row by row.
+    // If any part of the region is out of bounds, a BefungeError will be returned.
+    pub fn get_region(
+        &self,
+        top_left: Coord,
+        width: i64,
+        height: i64,
+    ) -> Result<Vec<Vec<char>>, BefungeError> {
+        self.check_region(&top_left, width, height)?;
+
+        let (x, y) = (top_left.x as usize, top_left.y as usize);
+
+        Ok(self.code_map[y..y + height as usize]
+            .iter()
+            .map(|row| row[x..x + width as usize].to_vec())
+            .collect())
+    }
+
+    // Writes the passed rows of characters into the playfield, starting at the passed
+    // top left corner. Rows may differ in length.
+    // If any part of the block is out of bounds, a BefungeError will be returned and
+    // the playfield is left unchanged.
+    pub fn set_region(&mut self, top_left: Coord, data: &[Vec<char>]) -> Result<(), BefungeError> {
+        let width = data.iter().map(Vec::len).max().unwrap_or(0);
+        self.check_region(&top_left, width as i64, data.len() as i64)?;
+
+        let (x, y) = (top_left.x as usize, top_left.y as usize);
+
+        for (row, values) in self.code_map[y..].iter_mut().zip(data) {
+            row[x..x + values.len()].copy_from_slice(values);
+        }
+        // Values that were written over are replaced by the new characters
+        self.wide_values.retain(|position, _| {
+            let row = usize::try_from(position.y - top_left.y)
+                .ok()
+                .and_then(|line| data.get(line));
+            let column = usize::try_from(position.x - top_left.x).ok();
+
+            !matches!((row, column), (Some(values), Some(column)) if column < values.len())
+        });
+
+        Ok(())
+    }
+
+    // Returns a BefungeError unless the region with the passed top left corner and
+    // size lies entirely inside the playfield
+    fn check_region(&self, top_left: &Coord, width: i64, height: i64) -> Result<(), BefungeError> {
+        let fits = |start: i64, length: i64, limit: i64| {
+            start >= 0 && length >= 0 && start.checked_add(length).is_some_and(|end| end <= limit)
+        };
+
+        if fits(top_left.x, width, self.dimensions.x) && fits(top_left.y, height, self.dimensions.y)
+        {
+            Ok(())
+        } else {
+            Err(BefungeError::OutOfBounds { coord: *top_left })
+        }
+    }
+
+    // Compares this playfield with another one of the same dimensions, returning the
+    // position of every cell that differs along with the character in this playfield
+    // and the character in the other one, in that order. Cells are reported row by row.
+    // If the dimensions of the playfields differ, `BefungeError::DimensionMismatch`
+    // will be returned.
+    pub fn diff(&self, other: &Playfield) -> Result<Vec<(Coord, char, char)>, BefungeError> {
+        if self.dimensions != other.dimensions {
+            return Err(BefungeError::DimensionMismatch {
+                expected: self.dimensions,
+                actual: other.dimensions,
+            });
+        }
+
+        let mut differences = Vec::new();
+
+        for (y, (row, other_row)) in self.code_map.iter().zip(&other.code_map).enumerate() {
+            for (x, (&cell, &other_cell)) in row.iter().zip(other_row).enumerate() {
+                if cell != other_cell {
+                    differences.push((
+                        Coord {
+                            x: x as i64,
+                            y: y as i64,
+                        },
+                        cell,
+                        other_cell,
+                    ));
+                }
+            }
+        }
+
+        Ok(differences)
+    }
+
+    // Renders the whole playfield in the given format. Every format ends with a newline.
+    pub fn render(&self, format: GridFormat) -> String {
+        let rows = self
+            .code_map
+            .iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>();
+
+        match format {
+            GridFormat::Plain => rows.iter().map(|row| format!("{}\n", row)).collect(),
+            GridFormat::Ruled => {
+                // Columns are labelled with the last digit of their index
+                let label_width = self.dimensions.y.saturating_sub(1).to_string().len();
+                let margin = " ".repeat(label_width + 1);
+                let border = format!("{}+{}+\n", margin, "-".repeat(self.dimensions.x as usize));
+
+                let mut rendered = format!(
+                    "{} {}\n",
+                    margin,
+                    (0..self.dimensions.x)
+                        .map(|x| std::char::from_digit((x % 10) as u32, 10).unwrap())
+                        .collect::<String>()
+                );
+                rendered.push_str(&border);
+                for (y, row) in rows.iter().enumerate() {
+                    rendered.push_str(&format!("{:>width$} |{}|\n", y, row, width = label_width));
+                }
+                rendered.push_str(&border);
+                rendered
+            }
+            #[cfg(feature = "json")]
+            GridFormat::Json => {
+                let grid = JsonGrid {
+                    width: self.dimensions.x,
+                    height: self.dimensions.y,
+                    rows: &rows,
+                };
+                format!("{}\n", serde_json::to_string(&grid).unwrap())
+            }
+        }
+    }
+
+    // Renders the cells within `radius` of `center` as text, one row per line.
+    // The excerpt is clipped to the playfield bounds instead of wrapping around.
+    // Each cell is padded by a space on both sides, except for the cell at `center`,
+    // which is surrounded by brackets to mark it.
+    pub fn render_excerpt(&self, center: &Coord, radius: i64) -> String {
+        let mut excerpt = String::new();
+
+        for y in (center.y - radius).max(0)..=(center.y + radius).min(self.dimensions.y - 1) {
+            for x in (center.x - radius).max(0)..=(center.x + radius).min(self.dimensions.x - 1) {
+                let (left, right) = if x == center.x && y == center.y {
+                    ('[', ']')
+                } else {
+                    (' ', ' ')
+                };
+
+                excerpt.push(left);
+                excerpt.push(self.code_map[y as usize][x as usize]);
+                excerpt.push(right);
+            }
+            excerpt.push('\n');
+        }
+
+        excerpt
+    }
+
+    // Returns the cells holding commands (anything but a space) that the program
+    // counter may reach when starting at the passed position and direction, sorted
+    // by row and then column. This is an over-approximation: every branch of ?, _
+    // and | is followed, and changes made by p are ignored.
+    pub fn reachable_cells(&self, start: &Coord, direction: &Direction) -> Vec<Coord> {
+        self.control_flow(start, direction).0
+    }
+
+    // Returns the edges between the cells returned by `self.reachable_cells`, where
+    // an edge means the program counter may move from the first cell to the second,
+    // passing only over spaces (or over a string, for ").
+    pub fn control_flow_edges(&self, start: &Coord, direction: &Direction) -> Vec<(Coord, Coord)> {
+        self.control_flow(start, direction).1
+    }
+
+    // Explores the program from the passed position and direction, returning the
+    // reachable command cells and the edges between them
+    fn control_flow(
+        &self,
+        start: &Coord,
+        direction: &Direction,
+    ) -> (Vec<Coord>, Vec<(Coord, Coord)>) {
+        let mut nodes = BTreeSet::new();
+        let mut edges = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = Vec::new();
+
+        if !self.contains(start) {
+            return (Vec::new(), Vec::new());
+        }
+
+        if let Some(first) = self.skip_spaces((start.x, start.y), direction_index(direction)) {
+            pending.push(first);
+        }
+
+        while let Some((position, direction)) = pending.pop() {
+            if !visited.insert((position, direction)) {
+                continue;
+            }
+            nodes.insert((position.1, position.0));
+
+            for (next_position, next_direction) in self.successors(position, direction) {
+                if let Some((node, node_direction)) =
+                    self.skip_spaces(next_position, next_direction)
+                {
+                    edges.insert(((position.1, position.0), (node.1, node.0)));
+                    pending.push((node, node_direction));
+                }
+            }
+        }
+
+        (
+            nodes.into_iter().map(|(y, x)| Coord { x, y }).collect(),
+            edges
+                .into_iter()
+                .map(|((y1, x1), (y2, x2))| (Coord { x: x1, y: y1 }, Coord { x: x2, y: y2 }))
+                .collect(),
+        )
+    }
+
+    // Returns the positions and directions the program counter may continue from
+    // after executing the command at the passed position in command mode
+    fn successors(&self, position: (i64, i64), direction: usize) -> Vec<((i64, i64), usize)> {
+        let (x, y) = position;
+
+        match self.code_map[y as usize][x as usize] {
+            '@' => Vec::new(),
+            '^' => vec![(self.step_from(position, UP), UP)],
+            'v' => vec![(self.step_from(position, DOWN), DOWN)],
+            '<' => vec![(self.step_from(position, LEFT), LEFT)],
+            '>' => vec![(self.step_from(position, RIGHT), RIGHT)],
+            '?' => [UP, DOWN, LEFT, RIGHT]
+                .iter()
+                .map(|&next| (self.step_from(position, next), next))
+                .collect(),
+            '_' => vec![
+                (self.step_from(position, LEFT), LEFT),
+                (self.step_from(position, RIGHT), RIGHT),
+            ],
+            '|' => vec![
+                (self.step_from(position, UP), UP),
+                (self.step_from(position, DOWN), DOWN),
+            ],
+            '#' => {
+                let skipped = self.step_from(position, direction);
+                vec![(self.step_from(skipped, direction), direction)]
+            }
+            '"' => {
+                // Move to the closing quote, which is this one if the string wraps
+                // all the way around
+                let mut closing = self.step_from(position, direction);
+                while self.code_map[closing.1 as usize][closing.0 as usize] != '"' {
+                    closing = self.step_from(closing, direction);
+                }
+                vec![(self.step_from(closing, direction), direction)]
+            }
+            _ => vec![(self.step_from(position, direction), direction)],
+        }
+    }
+
+    // Moves from the passed position in the passed direction over any spaces, and
+    // returns the first command reached. Returns `None` if only spaces are reachable.
+    fn skip_spaces(&self, position: (i64, i64), direction: usize) -> Option<((i64, i64), usize)> {
+        let mut current = position;
+        let limit = self.dimensions.x.max(self.dimensions.y);
+
+        for _ in 0..limit {
+            if self.code_map[current.1 as usize][current.0 as usize] != ' ' {
+                return Some((current, direction));
+            }
+            current = self.step_from(current, direction);
+        }
+        None
+    }
+
+    // Returns the position next to the passed one in the passed direction,
+    // wrapping around the edges
+    fn step_from(&self, position: (i64, i64), direction: usize) -> (i64, i64) {
+        let (dx, dy) = DIRECTION_OFFSETS[direction];
+
+        (
+            (position.0 + dx).rem_euclid(self.dimensions.x),
+            (position.1 + dy).rem_euclid(self.dimensions.y),
+        )
+    }
+
     // Updates the position of the program counter based on it's direction
     // and position. This method handles position wraparound (assuming
     // the width/height of the playfield is less than std::i64::MAX).
-    pub fn update_program_counter(&mut self) {
+    // Returns whether the program counter wrapped around an edge.
+    pub fn update_program_counter(&mut self) -> bool {
+        let position = &self.program_counter_position;
+        let wrapped = match self.program_counter_direction {
+            Direction::Up => position.y == 0,
+            Direction::Down => position.y == self.dimensions.y - 1,
+            Direction::Left => position.x == 0,
+            Direction::Right => position.x == self.dimensions.x - 1,
+        };
+
         self.program_counter_position = match self.program_counter_direction {
             Direction::Up => Coord {
                 x: self.program_counter_position.x,
@@ -158,6 +650,35 @@ impl Playfield {
                 y: self.program_counter_position.y,
             },
         };
+
+        wrapped
+    }
+}
+
+// Displays the current grid with each row on its own line, keeping the padding.
+// Unlike `Playfield::render`, there is no newline after the last row.
+impl fmt::Display for Playfield {
+    // Same as `GridFormat::Plain`, without the newline after the last row
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rendered = self.render(GridFormat::Plain);
+        write!(f, "{}", rendered.strip_suffix('\n').unwrap_or(&rendered))
+    }
+}
+
+// Indices of each direction in `DIRECTION_OFFSETS`, used by the control flow
+// analysis so that states can be hashed
+const UP: usize = 0;
+const DOWN: usize = 1;
+const LEFT: usize = 2;
+const RIGHT: usize = 3;
+const DIRECTION_OFFSETS: [(i64, i64); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+fn direction_index(direction: &Direction) -> usize {
+    match direction {
+        Direction::Up => UP,
+        Direction::Down => DOWN,
+        Direction::Left => LEFT,
+        Direction::Right => RIGHT,
     }
 }
 
@@ -191,6 +712,32 @@ mod tests {
             assert_eq!(playfield.dimensions, Coord { x: 6, y: 3 });
         }
 
+        #[test]
+        fn test_from_rows() {
+            let rows = vec!["abc".to_string(), "d".to_string(), "".to_string()];
+            let playfield =
+                Playfield::from_rows(rows, Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            assert_eq!(
+                playfield.code_map,
+                vec![
+                    vec!['a', 'b', 'c'],
+                    vec!['d', ' ', ' '],
+                    vec![' ', ' ', ' '],
+                ]
+            );
+            assert_eq!(playfield.dimensions, Coord { x: 3, y: 3 });
+        }
+
+        #[test]
+        fn test_null_padding() {
+            let playfield =
+                Playfield::with_padding("ab\nc", Coord { x: 0, y: 0 }, Direction::Right, '\0')
+                    .unwrap();
+
+            assert_eq!(playfield.code_map, vec![vec!['a', 'b'], vec!['c', '\0']]);
+        }
+
         #[test]
         fn test_empty() {
             let playfield = Playfield::new("", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
@@ -202,6 +749,69 @@ mod tests {
             assert_eq!(playfield.dimensions, Coord { x: 0, y: 0 });
         }
 
+        mod blank_rows {
+            use super::*;
+
+            fn setup_playfield(code: &str) -> Playfield {
+                Playfield::new(code, Coord { x: 0, y: 0 }, Direction::Right).unwrap()
+            }
+
+            #[test]
+            fn test_interior_blank_row() {
+                let playfield = setup_playfield("a\n\nb");
+
+                assert_eq!(playfield.code_map, vec![vec!['a'], vec![' '], vec!['b']]);
+                assert_eq!(playfield.dimensions, Coord { x: 1, y: 3 });
+            }
+
+            #[test]
+            fn test_leading_blank_rows() {
+                let playfield = setup_playfield("\n\nab");
+
+                assert_eq!(
+                    playfield.code_map,
+                    vec![vec![' ', ' '], vec![' ', ' '], vec!['a', 'b']]
+                );
+                assert_eq!(playfield.dimensions, Coord { x: 2, y: 3 });
+            }
+
+            #[test]
+            fn test_trailing_newline() {
+                // A trailing newline ends the last row instead of starting a new one
+                let playfield = setup_playfield("ab\ncd\n");
+
+                assert_eq!(playfield.code_map, vec![vec!['a', 'b'], vec!['c', 'd']]);
+                assert_eq!(playfield.dimensions, Coord { x: 2, y: 2 });
+            }
+
+            #[test]
+            fn test_trailing_blank_row() {
+                let playfield = setup_playfield("ab\n\n");
+
+                assert_eq!(playfield.code_map, vec![vec!['a', 'b'], vec![' ', ' ']]);
+                assert_eq!(playfield.dimensions, Coord { x: 2, y: 2 });
+            }
+
+            #[test]
+            fn test_only_blank_rows() {
+                let playfield = setup_playfield("\n\n\n");
+
+                assert!(playfield.code_map.is_empty());
+                assert_eq!(playfield.dimensions, Coord { x: 0, y: 0 });
+            }
+
+            #[test]
+            fn test_crlf_line_endings() {
+                let playfield = setup_playfield("ab\r\n\r\ncd\r\n");
+
+                assert_eq!(
+                    playfield.code_map,
+                    vec![vec!['a', 'b'], vec![' ', ' '], vec!['c', 'd']]
+                );
+                assert_eq!(playfield.dimensions, Coord { x: 2, y: 3 });
+            }
+        }
+
         #[test]
         fn test_single_row() {
             let playfield =
@@ -316,6 +926,19 @@ mod tests {
     mod set_character_at {
         use super::*;
 
+        #[test]
+        fn test_just_out_of_bounds() {
+            let mut playfield =
+                Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            assert!(playfield
+                .set_character_at(&Coord { x: 2, y: 0 }, '#')
+                .is_err());
+            assert!(playfield
+                .set_character_at(&Coord { x: 0, y: 2 }, '#')
+                .is_err());
+        }
+
         #[test]
         fn test_basic() {
             let mut playfield =
@@ -342,6 +965,15 @@ mod tests {
     mod get_character_at {
         use super::*;
 
+        #[test]
+        fn test_just_out_of_bounds() {
+            let playfield =
+                Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            assert!(playfield.get_character_at(&Coord { x: 2, y: 0 }).is_err());
+            assert!(playfield.get_character_at(&Coord { x: 0, y: 2 }).is_err());
+        }
+
         #[test]
         fn test_basic() {
             let playfield =
@@ -363,6 +995,473 @@ mod tests {
         }
     }
 
+    mod resize {
+        use super::*;
+
+        #[test]
+        fn test_grow() {
+            let mut playfield =
+                Playfield::new("ab\ncd", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            playfield.resize(3, 3, '.').unwrap();
+
+            assert_eq!(
+                playfield.code_map,
+                vec![['a', 'b', '.'], ['c', 'd', '.'], ['.', '.', '.']]
+            );
+            assert_eq!(playfield.dimensions, Coord { x: 3, y: 3 });
+        }
+
+        #[test]
+        fn test_shrink() {
+            let mut playfield =
+                Playfield::new("abc\ndef\nghi", Coord { x: 1, y: 1 }, Direction::Right).unwrap();
+
+            playfield.resize(2, 2, ' ').unwrap();
+
+            assert_eq!(playfield.code_map, vec![['a', 'b'], ['d', 'e']]);
+            assert_eq!(playfield.dimensions, Coord { x: 2, y: 2 });
+        }
+
+        #[test]
+        fn test_shrink_past_program_counter() {
+            let mut playfield =
+                Playfield::new("abc\ndef\nghi", Coord { x: 2, y: 0 }, Direction::Right).unwrap();
+
+            match playfield.resize(2, 3, ' ') {
+                Err(BefungeError::OutOfBounds { coord }) => {
+                    assert_eq!(coord, Coord { x: 2, y: 0 })
+                }
+                result => panic!("Expected an out of bounds error, got {:?}", result),
+            }
+            assert_eq!(playfield.dimensions, Coord { x: 3, y: 3 });
+        }
+    }
+
+    mod get_region {
+        use super::*;
+
+        #[test]
+        fn test_basic() {
+            let playfield =
+                Playfield::new("abc\ndef\nghi", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            let region = playfield.get_region(Coord { x: 1, y: 1 }, 2, 2).unwrap();
+
+            assert_eq!(region, vec![vec!['e', 'f'], vec!['h', 'i']]);
+        }
+
+        #[test]
+        fn test_out_of_bounds_access() {
+            let playfield =
+                Playfield::new("abc\ndef\nghi", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            assert!(playfield.get_region(Coord { x: 2, y: 2 }, 2, 2).is_err());
+            assert!(playfield.get_region(Coord { x: -1, y: 0 }, 2, 2).is_err());
+            assert!(playfield.get_region(Coord { x: 0, y: 0 }, -1, 2).is_err());
+        }
+    }
+
+    mod values {
+        use super::*;
+
+        fn setup_playfield() -> Playfield {
+            Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Right).unwrap()
+        }
+
+        #[test]
+        fn test_character_value() {
+            let mut playfield = setup_playfield();
+            assert_eq!(
+                playfield.get_value_at(&Coord { x: 1, y: 0 }).unwrap(),
+                'w' as i64
+            );
+
+            playfield.set_value_at(&Coord { x: 1, y: 0 }, 300).unwrap();
+            assert_eq!(playfield.get_value_at(&Coord { x: 1, y: 0 }).unwrap(), 300);
+            assert_eq!(
+                playfield.get_character_at(&Coord { x: 1, y: 0 }).unwrap(),
+                '\u{12c}'
+            );
+        }
+
+        #[test]
+        fn test_wide_value() {
+            let mut playfield = setup_playfield();
+            let position = Coord { x: 0, y: 1 };
+
+            playfield.set_value_at(&position, -300).unwrap();
+            assert_eq!(playfield.get_value_at(&position).unwrap(), -300);
+            assert_eq!(
+                playfield.get_character_at(&position).unwrap(),
+                char::REPLACEMENT_CHARACTER
+            );
+
+            playfield.set_character_at(&position, 'a').unwrap();
+            assert_eq!(playfield.get_value_at(&position).unwrap(), 'a' as i64);
+        }
+
+        #[test]
+        fn test_wide_value_overwritten() {
+            let mut playfield = setup_playfield();
+            let position = Coord { x: 1, y: 1 };
+
+            playfield.set_value_at(&position, -1).unwrap();
+            playfield
+                .set_region(Coord { x: 1, y: 0 }, &[vec!['a'], vec!['b']])
+                .unwrap();
+            assert_eq!(playfield.get_value_at(&position).unwrap(), 'b' as i64);
+
+            playfield.set_value_at(&position, -1).unwrap();
+            playfield.resize(1, 2, ' ').unwrap();
+            playfield.resize(2, 2, ' ').unwrap();
+            assert_eq!(playfield.get_value_at(&position).unwrap(), ' ' as i64);
+        }
+
+        #[test]
+        fn test_out_of_bounds() {
+            let mut playfield = setup_playfield();
+
+            assert!(playfield.set_value_at(&Coord { x: 2, y: 0 }, 300).is_err());
+            assert!(playfield.get_value_at(&Coord { x: 2, y: 0 }).is_err());
+        }
+    }
+
+    mod set_region {
+        use super::*;
+
+        #[test]
+        fn test_basic() {
+            let mut playfield =
+                Playfield::new("abc\ndef\nghi", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            playfield
+                .set_region(Coord { x: 0, y: 1 }, &[vec!['1', '2'], vec!['3', '4']])
+                .unwrap();
+
+            assert_eq!(
+                playfield.get_character_at(&Coord { x: 0, y: 1 }).unwrap(),
+                '1'
+            );
+            assert_eq!(
+                playfield.get_character_at(&Coord { x: 1, y: 1 }).unwrap(),
+                '2'
+            );
+            assert_eq!(
+                playfield.get_character_at(&Coord { x: 0, y: 2 }).unwrap(),
+                '3'
+            );
+            assert_eq!(
+                playfield.get_character_at(&Coord { x: 1, y: 2 }).unwrap(),
+                '4'
+            );
+            assert_eq!(
+                playfield.get_character_at(&Coord { x: 2, y: 2 }).unwrap(),
+                'i'
+            );
+        }
+
+        #[test]
+        fn test_out_of_bounds_access() {
+            let mut playfield =
+                Playfield::new("abc\ndef\nghi", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            let return_value =
+                playfield.set_region(Coord { x: 2, y: 1 }, &[vec!['1', '2'], vec!['3', '4']]);
+
+            assert!(return_value.is_err());
+            assert_eq!(
+                playfield.code_map,
+                vec![['a', 'b', 'c'], ['d', 'e', 'f'], ['g', 'h', 'i']]
+            );
+        }
+    }
+
+    mod diff {
+        use super::*;
+
+        #[test]
+        fn test_identical() {
+            let playfield =
+                Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+            let other = Playfield::new("lw\ngg", Coord { x: 1, y: 1 }, Direction::Up).unwrap();
+
+            assert!(playfield.diff(&other).unwrap().is_empty());
+        }
+
+        #[test]
+        fn test_one_changed_cell() {
+            let playfield =
+                Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+            let mut other =
+                Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            other.set_character_at(&Coord { x: 1, y: 1 }, '#').unwrap();
+
+            assert_eq!(
+                playfield.diff(&other).unwrap(),
+                vec![(Coord { x: 1, y: 1 }, 'g', '#')]
+            );
+        }
+
+        #[test]
+        fn test_different_dimensions() {
+            let playfield =
+                Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+            let other = Playfield::new("lw", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            match playfield.diff(&other) {
+                Err(BefungeError::DimensionMismatch { expected, actual }) => {
+                    assert_eq!(expected, Coord { x: 2, y: 2 });
+                    assert_eq!(actual, Coord { x: 2, y: 1 });
+                }
+                result => panic!("Expected mismatched dimensions, got {:?}", result),
+            }
+        }
+    }
+
+    mod render {
+        use super::*;
+
+        #[test]
+        fn test_plain() {
+            let playfield =
+                Playfield::new("lw\ng", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            assert_eq!(playfield.render(GridFormat::Plain), "lw\ng \n");
+        }
+
+        #[test]
+        fn test_ruled() {
+            let playfield =
+                Playfield::new("lw\ng", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            assert_eq!(
+                playfield.render(GridFormat::Ruled),
+                "   01\n  +--+\n0 |lw|\n1 |g |\n  +--+\n"
+            );
+        }
+
+        #[test]
+        #[cfg(feature = "json")]
+        fn test_json() {
+            let playfield = Playfield::new(
+                "64+\"!dlroW ,olleH\">:#,_@\n\\\t",
+                Coord { x: 0, y: 0 },
+                Direction::Right,
+            )
+            .unwrap();
+
+            let dump: serde_json::Value =
+                serde_json::from_str(&playfield.render(GridFormat::Json)).unwrap();
+
+            assert_eq!(dump["width"], 24);
+            assert_eq!(dump["height"], 2);
+            assert_eq!(
+                dump["rows"],
+                serde_json::json!(["64+\"!dlroW ,olleH\">:#,_@", "\\\t                      "])
+            );
+        }
+    }
+
+    mod display {
+        use super::*;
+
+        #[test]
+        fn test_basic() {
+            let mut playfield =
+                Playfield::new("lw\ng", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+            playfield
+                .set_character_at(&Coord { x: 1, y: 1 }, '#')
+                .unwrap();
+
+            assert_eq!(playfield.to_string(), "lw\ng#");
+        }
+
+        #[test]
+        fn test_padding() {
+            let playfield =
+                Playfield::new("a\n\nbcd", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            assert_eq!(playfield.to_string(), "a  \n   \nbcd");
+        }
+
+        #[test]
+        fn test_empty() {
+            let playfield = Playfield::new("", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            assert_eq!(playfield.to_string(), "");
+        }
+    }
+
+    mod render_excerpt {
+        use super::*;
+
+        #[test]
+        fn test_basic() {
+            let playfield = Playfield::new(
+                "abcdefg\nhijklmn\nopqrstu\nvwxyz01\n2345678\n9ABCDEF",
+                Coord { x: 0, y: 0 },
+                Direction::Right,
+            )
+            .unwrap();
+
+            let excerpt = playfield.render_excerpt(&Coord { x: 3, y: 2 }, 2);
+
+            assert_eq!(
+                excerpt,
+                " b  c  d  e  f \n i  j  k  l  m \n p  q [r] s  t \n w  x  y  z  0 \n 3  4  5  6  7 \n"
+            );
+        }
+
+        #[test]
+        fn test_clipped_at_edges() {
+            let playfield =
+                Playfield::new("abc\ndef\nghi", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+            let excerpt = playfield.render_excerpt(&Coord { x: 0, y: 0 }, 2);
+
+            assert_eq!(excerpt, "[a] b  c \n d  e  f \n g  h  i \n");
+        }
+    }
+
+    #[test]
+    fn test_quote_counts() {
+        let playfield =
+            Playfield::new("\"a\"\n\n\"", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+        assert_eq!(playfield.quote_counts(), [2, 0, 1]);
+    }
+
+    #[test]
+    fn test_memory_footprint() {
+        let small = Playfield::new("ab\ncd", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+        let large = Playfield::new(
+            "abcd\nefgh\nijkl\nmnop",
+            Coord { x: 0, y: 0 },
+            Direction::Right,
+        )
+        .unwrap();
+
+        assert!(small.memory_footprint() >= 4 * mem::size_of::<char>());
+        assert!(large.memory_footprint() >= 16 * mem::size_of::<char>());
+        assert!(large.memory_footprint() > small.memory_footprint());
+    }
+
+    mod control_flow {
+        use super::*;
+
+        fn setup_playfield(code: &str) -> Playfield {
+            Playfield::new(code, Coord { x: 0, y: 0 }, Direction::Right).unwrap()
+        }
+
+        #[test]
+        fn test_straight_line() {
+            let playfield = setup_playfield("1 2@3");
+            let start = Coord { x: 0, y: 0 };
+
+            assert_eq!(
+                playfield.reachable_cells(&start, &Direction::Right),
+                [
+                    Coord { x: 0, y: 0 },
+                    Coord { x: 2, y: 0 },
+                    Coord { x: 3, y: 0 }
+                ]
+            );
+            assert_eq!(
+                playfield.control_flow_edges(&start, &Direction::Right),
+                [
+                    (Coord { x: 0, y: 0 }, Coord { x: 2, y: 0 }),
+                    (Coord { x: 2, y: 0 }, Coord { x: 3, y: 0 }),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_branches() {
+            let playfield = setup_playfield("v  \n_@1\n2  ");
+            let reachable = playfield.reachable_cells(&Coord { x: 0, y: 0 }, &Direction::Right);
+
+            // _ goes left, wrapping to 1, and right to @. 2 is never reached.
+            assert_eq!(
+                reachable,
+                [
+                    Coord { x: 0, y: 0 },
+                    Coord { x: 0, y: 1 },
+                    Coord { x: 1, y: 1 },
+                    Coord { x: 2, y: 1 },
+                ]
+            );
+        }
+
+        #[test]
+        fn test_bridge_and_string() {
+            let playfield = setup_playfield("#1\"2\"3@");
+            let reachable = playfield.reachable_cells(&Coord { x: 0, y: 0 }, &Direction::Right);
+
+            assert_eq!(
+                reachable,
+                [
+                    Coord { x: 0, y: 0 },
+                    Coord { x: 2, y: 0 },
+                    Coord { x: 5, y: 0 },
+                    Coord { x: 6, y: 0 },
+                ]
+            );
+        }
+
+        #[test]
+        fn test_only_spaces() {
+            let playfield = setup_playfield("  \n  ");
+            let start = Coord { x: 0, y: 0 };
+
+            assert!(playfield
+                .reachable_cells(&start, &Direction::Right)
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn test_iter_cells() {
+        let playfield = Playfield::new("ab\nc", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+        assert_eq!(
+            playfield.iter_cells().collect::<Vec<_>>(),
+            vec![
+                (Coord { x: 0, y: 0 }, 'a'),
+                (Coord { x: 1, y: 0 }, 'b'),
+                (Coord { x: 0, y: 1 }, 'c'),
+                (Coord { x: 1, y: 1 }, ' '),
+            ]
+        );
+
+        let playfield = Playfield::new("", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+        assert_eq!(playfield.iter_cells().count(), 0);
+    }
+
+    #[test]
+    fn test_coord_as_map_key() {
+        use std::collections::HashMap;
+
+        let playfield = Playfield::new("ab\nca", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+        let cells = playfield.iter_cells().collect::<HashMap<_, _>>();
+
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells[&Coord { x: 1, y: 0 }], 'b');
+        assert_eq!(cells[&Coord { x: 1, y: 1 }], 'a');
+        assert!(!cells.contains_key(&Coord { x: 2, y: 0 }));
+    }
+
+    #[test]
+    fn test_contains() {
+        let playfield = Playfield::new("ab\ncd", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
+
+        assert!(playfield.contains(&Coord { x: 0, y: 0 }));
+        assert!(playfield.contains(&Coord { x: 1, y: 1 }));
+        assert!(!playfield.contains(&Coord { x: 2, y: 0 }));
+        assert!(!playfield.contains(&Coord { x: 0, y: 2 }));
+        assert!(!playfield.contains(&Coord { x: -1, y: 0 }));
+    }
+
     mod update_program_counter {
         use super::*;
 
@@ -371,7 +1470,7 @@ mod tests {
             let mut playfield =
                 Playfield::new("lw\ngg", Coord { x: 0, y: 1 }, Direction::Up).unwrap();
 
-            playfield.update_program_counter();
+            assert!(!playfield.update_program_counter());
 
             assert_eq!(playfield.program_counter_position, Coord { x: 0, y: 0 });
         }
@@ -381,7 +1480,7 @@ mod tests {
             let mut playfield =
                 Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Up).unwrap();
 
-            playfield.update_program_counter();
+            assert!(playfield.update_program_counter());
 
             assert_eq!(playfield.program_counter_position, Coord { x: 0, y: 1 });
         }
@@ -391,7 +1490,7 @@ mod tests {
             let mut playfield =
                 Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Down).unwrap();
 
-            playfield.update_program_counter();
+            assert!(!playfield.update_program_counter());
 
             assert_eq!(playfield.program_counter_position, Coord { x: 0, y: 1 });
         }
@@ -401,7 +1500,7 @@ mod tests {
             let mut playfield =
                 Playfield::new("lw\ngg", Coord { x: 0, y: 1 }, Direction::Down).unwrap();
 
-            playfield.update_program_counter();
+            assert!(playfield.update_program_counter());
 
             assert_eq!(playfield.program_counter_position, Coord { x: 0, y: 0 });
         }
@@ -411,7 +1510,7 @@ mod tests {
             let mut playfield =
                 Playfield::new("lw\ngg", Coord { x: 1, y: 0 }, Direction::Left).unwrap();
 
-            playfield.update_program_counter();
+            assert!(!playfield.update_program_counter());
 
             assert_eq!(playfield.program_counter_position, Coord { x: 0, y: 0 });
         }
@@ -421,7 +1520,7 @@ mod tests {
             let mut playfield =
                 Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Left).unwrap();
 
-            playfield.update_program_counter();
+            assert!(playfield.update_program_counter());
 
             assert_eq!(playfield.program_counter_position, Coord { x: 1, y: 0 });
         }
@@ -431,7 +1530,7 @@ mod tests {
             let mut playfield =
                 Playfield::new("lw\ngg", Coord { x: 0, y: 0 }, Direction::Right).unwrap();
 
-            playfield.update_program_counter();
+            assert!(!playfield.update_program_counter());
 
             assert_eq!(playfield.program_counter_position, Coord { x: 1, y: 0 });
         }
@@ -441,7 +1540,7 @@ mod tests {
             let mut playfield =
                 Playfield::new("lw\ngg", Coord { x: 1, y: 0 }, Direction::Right).unwrap();
 
-            playfield.update_program_counter();
+            assert!(playfield.update_program_counter());
 
             assert_eq!(playfield.program_counter_position, Coord { x: 0, y: 0 });
         }
@@ -0,0 +1,60 @@
+/* befunge/samples.rs - Contains well-known Befunge-93 sample programs
+ * Copyright 2018 Arnav Borborah
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// Well-known Befunge-93 programs, taken from
+// https://esolangs.org/wiki/Befunge#Befunge-93_and_Befunge-98
+
+// Prints "Hello, World!" followed by a newline
+pub const HELLO_WORLD: &str = "64+\"!dlroW ,olleH\">:#,_@";
+
+// Reads a number with & and prints its factorial
+pub const FACTORIAL: &str = "&>:1-:v v *_$.@\n ^    _$>\\:^";
+
+// Prints the primes below 80, using the last row of the grid as the sieve
+pub const SIEVE_OF_ERATOSTHENES: &str = "2>:3g\" \"-!v\\  g30          <\n |!`\"O\":+1_:.:03p>03g+:\"O\"`|\n @               ^  p3\\\" \":<\n2 234567890123456789012345678901234567890123456789012345678901234567890123456789";
+
+// Prints its own source by reading it from the grid with g
+pub const QUINE: &str = "01->1# +# :# 0# g# ,# :# 5# 8# *# 4# +# -# _@";
+
+// Prints its own source, which spans two lines, by pushing it as a string.
+// The output ends with a trailing space, since the final 0 is printed with `.`.
+pub const MULTILINE_QUINE: &str = "0 v\n \"<@_ #! #: #,<*2-1*92,*84,*25,+*92*4*55.0";
+
+// Every sample program along with its name
+pub const ALL: &[(&str, &str)] = &[
+    ("HELLO_WORLD", HELLO_WORLD),
+    ("FACTORIAL", FACTORIAL),
+    ("SIEVE_OF_ERATOSTHENES", SIEVE_OF_ERATOSTHENES),
+    ("QUINE", QUINE),
+    ("MULTILINE_QUINE", MULTILINE_QUINE),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::super::Interpreter;
+    use super::*;
+
+    #[test]
+    fn test_all_samples_run() {
+        for &(name, code) in ALL {
+            // Only FACTORIAL reads input
+            let mut interpreter =
+                Interpreter::new(code, Vec::new(), "5\n".as_bytes(), None, None).unwrap();
+
+            assert!(interpreter.halts_within(100_000), "{} did not halt", name);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+/* befunge/stack.rs - Contains the struct definition of the Befunge-93 stack
+ * Copyright 2018 Arnav Borborah
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// Represents the Befunge-93 stack. Besides the values themselves, it keeps
+// track of the deepest the stack has been, which helps with choosing an
+// initial capacity for push-heavy programs.
+#[derive(Debug, Default)]
+pub struct Stack {
+    values: Vec<i64>,
+    peak_depth: usize,
+    changes: u64,
+}
+
+impl Stack {
+    // Creates an empty stack
+    pub fn new() -> Stack {
+        Stack::default()
+    }
+
+    // Creates an empty stack that can hold `capacity` values before reallocating
+    pub fn with_capacity(capacity: usize) -> Stack {
+        Stack {
+            values: Vec::with_capacity(capacity),
+            peak_depth: 0,
+            changes: 0,
+        }
+    }
+
+    // Pushes a value onto the top of the stack
+    pub fn push(&mut self, value: i64) {
+        self.values.push(value);
+        self.peak_depth = self.peak_depth.max(self.values.len());
+        self.changes += 1;
+    }
+
+    // Pops the value on the top of the stack. Popping an empty stack yields 0.
+    pub fn pop(&mut self) -> i64 {
+        match self.values.pop() {
+            Some(value) => {
+                self.changes += 1;
+                value
+            }
+            None => 0,
+        }
+    }
+
+    // Returns the value on the top of the stack, if there is one
+    pub fn last(&self) -> Option<&i64> {
+        self.values.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    // Returns the number of values the stack can hold before reallocating
+    pub fn capacity(&self) -> usize {
+        self.values.capacity()
+    }
+
+    // Returns the largest number of values the stack has held at once
+    pub fn peak_depth(&self) -> usize {
+        self.peak_depth
+    }
+
+    // Returns the number of pushes and pops that changed the stack so far. Popping
+    // an empty stack leaves it unchanged, so it is not counted.
+    pub fn changes(&self) -> u64 {
+        self.changes
+    }
+
+    // Returns the values on the stack, from bottom to top
+    pub fn as_slice(&self) -> &[i64] {
+        &self.values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pop_empty() {
+        let mut stack = Stack::new();
+
+        assert_eq!(stack.pop(), 0);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_peak_depth() {
+        let mut stack = Stack::new();
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        stack.pop();
+        stack.pop();
+        stack.push(4);
+
+        assert_eq!(stack.as_slice(), [1, 4]);
+        assert_eq!(stack.peak_depth(), 3);
+    }
+
+    #[test]
+    fn test_changes() {
+        let mut stack = Stack::new();
+
+        stack.push(1);
+        stack.pop();
+        stack.pop();
+
+        assert_eq!(stack.changes(), 2);
+    }
+
+    #[test]
+    fn test_with_capacity_does_not_reallocate() {
+        let mut stack = Stack::with_capacity(1000);
+        let initial_capacity = stack.capacity();
+
+        for value in 0..1000 {
+            stack.push(value);
+        }
+
+        assert_eq!(stack.capacity(), initial_capacity);
+        assert_eq!(stack.peak_depth(), 1000);
+    }
+}
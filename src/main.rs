@@ -14,113 +14,1528 @@
  * limitations under the License.
  */
 
+extern crate bef93;
 #[macro_use]
 extern crate clap;
-extern crate rand;
+#[cfg(feature = "flate2")]
+extern crate flate2;
+#[cfg(feature = "json")]
+extern crate serde;
+#[cfg(feature = "json")]
+extern crate serde_json;
 
-mod befunge;
+use bef93::befunge;
+#[cfg(feature = "flate2")]
+use flate2::read::GzDecoder;
+#[cfg(feature = "json")]
+use serde::Serialize;
 
-use std::env::current_dir;
-use std::fs::read_to_string;
-use std::io::Write;
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::env::{self, current_dir};
+use std::ffi::{OsStr, OsString};
+use std::fs::{canonicalize, read_to_string, File};
+use std::io::{BufRead, BufReader, BufWriter, Cursor, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{error, io, process};
 
 fn main() {
-    let mut exit_code = 0;
-
-    // Error handling code
-    if let Err(err) = cli() {
-        exit_code = if let Some(clap_err) = err.downcast_ref::<clap::Error>() {
-            // Clap CLI errors
-            // Don't exit with 1 if help or version information are being displayed
-            match clap_err.kind {
-                clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => {
-                    print!("{}", clap_err);
-
-                    io::stdout()
-                        .flush()
-                        .unwrap_or_else(|_| eprintln!("Unable to flush stdout!"));
-                    0
-                }
-                _ => {
-                    eprint!("{}", clap_err);
+    let result = run_cli(env::args_os());
 
-                    io::stdout()
-                        .flush()
-                        .unwrap_or_else(|_| eprintln!("Unable to flush stdout!"));
-                    1
-                }
-            }
-        } else if let Some(befunge_err) = err.downcast_ref::<befunge::Error>() {
-            // Befunge-93 code errors
+    print!("{}", result.stdout);
+    eprint!("{}", result.diagnostics);
 
-            eprintln!("Befunge-93 Error: {}", befunge_err);
-            1
-        } else if let Some(io_err) = err.downcast_ref::<io::Error>() {
-            // IO Errors
+    io::stdout()
+        .flush()
+        .unwrap_or_else(|_| eprintln!("Unable to flush stdout!"));
 
-            eprintln!("IO Error: {}", io_err);
-            1
-        } else {
-            // Unknown error
+    process::exit(result.exit_code);
+}
 
-            eprintln!("Unknown error: {}", err);
-            1
-        };
-    }
+// The outcome of running the CLI, which `main` turns into the output and exit code
+// of the process. Diagnostics printed while the program runs go straight to stderr
+// and are not part of it.
+#[derive(Debug)]
+struct RunResult {
+    // The code the process exits with
+    exit_code: i32,
+    // Text for stdout, like help or version information
+    stdout: String,
+    // Text for stderr describing why the CLI failed
+    diagnostics: String,
+}
+
+// Runs the CLI with the passed arguments, the first of which is the name of the
+// binary, and maps any error to its exit code and diagnostic. The exit codes are:
+//
+// 0 - The program halted, or help or version information was printed
+// 1 - The arguments were invalid, or another error occurred
+// 2 - The program failed with a Befunge-93 error, like dividing by zero
+// 3 - The program did not halt within the step limit set with --max-steps, or
+//     executed p more often than --max-put-writes allows
+// 4 - An IO error occurred, like a file that could not be read
+fn run_cli<Args, Arg>(args: Args) -> RunResult
+where
+    Args: IntoIterator<Item = Arg>,
+    Arg: Into<OsString> + Clone,
+{
+    let err = match cli(args) {
+        Ok(()) => {
+            return RunResult {
+                exit_code: 0,
+                stdout: String::new(),
+                diagnostics: String::new(),
+            }
+        }
+        Err(err) => err,
+    };
 
-    process::exit(exit_code);
+    if let Some(clap_err) = err.downcast_ref::<clap::Error>() {
+        // Clap CLI errors
+        // Don't exit with 1 if help or version information are being displayed
+        match clap_err.kind {
+            clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => RunResult {
+                exit_code: 0,
+                stdout: clap_err.to_string(),
+                diagnostics: String::new(),
+            },
+            _ => RunResult {
+                exit_code: 1,
+                stdout: String::new(),
+                diagnostics: clap_err.to_string(),
+            },
+        }
+    } else if let Some(befunge_err) = err.downcast_ref::<befunge::Error>() {
+        // Befunge-93 code errors
+        RunResult {
+            exit_code: match befunge_err {
+                befunge::Error::StepLimitExceeded { .. }
+                | befunge::Error::PutLimitExceeded { .. } => 3,
+                _ => 2,
+            },
+            stdout: String::new(),
+            diagnostics: format!("Befunge-93 Error: {}\n", befunge_err),
+        }
+    } else if let Some(io_err) = err.downcast_ref::<io::Error>() {
+        // IO Errors
+        RunResult {
+            exit_code: 4,
+            stdout: String::new(),
+            diagnostics: format!("IO Error: {}\n", io_err),
+        }
+    } else {
+        // Unknown error
+        RunResult {
+            exit_code: 1,
+            stdout: String::new(),
+            diagnostics: format!("Unknown error: {}\n", err),
+        }
+    }
 }
 
-fn cli() -> Result<(), Box<dyn error::Error>> {
+fn cli<Args, Arg>(args: Args) -> Result<(), Box<dyn error::Error>>
+where
+    Args: IntoIterator<Item = Arg>,
+    Arg: Into<OsString> + Clone,
+{
     let matches = clap::App::new(crate_name!())
         .version(crate_version!())
         .author(crate_authors!())
         .about("A Befunge-93 interpreter supporting an extended grid")
         .arg(
             clap::Arg::with_name("FILE")
-                .help("A file with Befunge-93 source code")
-                .required(true),
+                .help(
+                    "A file with Befunge-93 source code, or - to read the program from stdin. \
+                     Since the whole of stdin is then the program, input for & and ~ has to \
+                     come from a file passed with -i",
+                )
+                .required_unless("eval"),
+        )
+        .arg(
+            clap::Arg::with_name("eval")
+                .short("e")
+                .long("eval")
+                .value_name("CODE")
+                .conflicts_with_all(&["FILE", "compare-with"])
+                .help("Runs CODE as a Befunge-93 program instead of reading it from FILE"),
+        )
+        .arg(
+            clap::Arg::with_name("force")
+                .long("force")
+                .help(
+                    "Runs FILE whatever its extension, instead of only accepting \
+                     .bf, .b93, .bef and .befunge files",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("PATH")
+                .help("Writes the output of the program to PATH instead of stdout"),
+        )
+        .arg(
+            clap::Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .value_name("PATH")
+                .conflicts_with("split-input")
+                .help("Reads the input of the program from PATH instead of stdin"),
+        )
+        .arg(
+            clap::Arg::with_name("verbose")
+                .short("v")
+                .multiple(true)
+                .help(
+                    "Prints diagnostics to stderr. -v prints a summary once the program halts, \
+                     -vv also logs every change of mode or direction, \
+                     and -vvv also traces every step",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("verbose-errors")
+                .long("verbose-errors")
+                .help("Prints the grid surrounding the program counter when a Befunge-93 error occurs"),
+        )
+        .arg(
+            clap::Arg::with_name("stack-capacity")
+                .long("stack-capacity")
+                .value_name("N")
+                .help("Reserves space for N values on the stack before execution starts"),
+        )
+        .arg(
+            clap::Arg::with_name("stack-file")
+                .long("stack-file")
+                .visible_alias("load-stack")
+                .value_name("PATH")
+                .help(
+                    "Pushes the whitespace separated integers in PATH onto the stack before \
+                     execution starts, in order, so the last integer is on top",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("save-stack")
+                .long("save-stack")
+                .value_name("PATH")
+                .help(
+                    "Saves the stack to PATH after running, one integer per line from the \
+                     bottom, so another run can load it with --stack-file",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("comparison")
+                .long("comparison")
+                .value_name("MODE")
+                .possible_values(&["signed", "byte"])
+                .help("Compares the operands of ` as signed integers (default) or as unsigned bytes"),
+        )
+        .arg(
+            clap::Arg::with_name("division")
+                .long("division")
+                .value_name("MODE")
+                .possible_values(&["truncated", "floored"])
+                .help(
+                    "Rounds the quotient of / toward zero (default) or toward negative infinity. \
+                     % always uses the same rounding, so its result has the sign of the \
+                     dividend or of the divisor respectively.",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("floored-division")
+                .long("floored-division")
+                .conflicts_with("division")
+                .help("Same as --division floored, which matches Python's // and %"),
+        )
+        .arg(
+            clap::Arg::with_name("progress")
+                .long("progress")
+                .help("Periodically prints the number of executed steps to stderr"),
+        )
+        .arg(
+            clap::Arg::with_name("progress-interval")
+                .long("progress-interval")
+                .value_name("N")
+                .default_value("1000000")
+                .help("The number of steps between progress updates"),
+        )
+        .arg(
+            clap::Arg::with_name("strict-halt")
+                .long("strict-halt")
+                .help("Treats long runs of spaces as an error, since the program may never reach @"),
+        )
+        .arg(
+            clap::Arg::with_name("strict-halt-limit")
+                .long("strict-halt-limit")
+                .value_name("N")
+                .default_value("1000")
+                .help("The number of consecutive spaces allowed by --strict-halt"),
+        )
+        .arg(
+            clap::Arg::with_name("max-put-writes")
+                .long("max-put-writes")
+                .value_name("N")
+                .help("Stops the program with an error once it executes p more than N times"),
+        )
+        .arg(
+            clap::Arg::with_name("swap-pg-coords")
+                .long("swap-pg-coords")
+                .help(
+                    "Makes g and p pop x before y instead of y before x, \
+                     for programs written for interpreters using that order",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("autoflush")
+                .long("autoflush")
+                .help(
+                    "Flushes the output after every write instead of when the program stops \
+                     or waits for input. This is the default when stdout is a terminal",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("no-trailing-space")
+                .long("no-trailing-space")
+                .help("Makes . write just the number, without a space after it"),
+        )
+        .arg(
+            clap::Arg::with_name("strict-ascii")
+                .long("strict-ascii")
+                .help("Makes , fail on values outside of ASCII (0-127) instead of writing them"),
+        )
+        .arg(
+            clap::Arg::with_name("cell-range")
+                .long("cell-range")
+                .value_name("RANGE")
+                .possible_values(&["byte", "ascii"])
+                .help(
+                    "Makes , and p accept values between 0 and 255 (default), \
+                     or only ASCII values between 0 and 127",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("pad-with")
+                .long("pad-with")
+                .value_name("VALUE")
+                .possible_values(&["space", "null"])
+                .help(
+                    "Pads short lines with spaces (default) or null characters, \
+                     which g reads as 0 and are executed like spaces",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("char-input")
+                .long("char-input")
+                .value_name("MODE")
+                .possible_values(&["unicode", "ascii", "byte"])
+                .help(
+                    "Makes ~ read a line with a single Unicode character (default), \
+                     a line with a single ASCII character, or a single byte",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("ascii-only-input")
+                .long("ascii-only-input")
+                .conflicts_with("char-input")
+                .help(
+                    "Makes ~ read a line with a single byte and push its value (0-255), \
+                     so raw bytes such as Latin-1 text can be read. A line with more than \
+                     one byte, such as a multibyte UTF-8 character, is an error",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("int-separators")
+                .long("int-separators")
+                .value_name("CHARS")
+                .help(
+                    "Removes every character in CHARS from the input of & before parsing it, \
+                     so \"1,000\" can be read with --int-separators ,",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("dump-format")
+                .long("dump-format")
+                .value_name("FORMAT")
+                .possible_values(&["plain", "ruled", "json"])
+                .help("Prints the final grid to stderr as plain text, ruled text, or JSON"),
+        )
+        .arg(
+            clap::Arg::with_name("dump-grid")
+                .long("dump-grid")
+                .conflicts_with("dump-format")
+                .help(
+                    "Prints the final grid to stderr, including any changes made with p. \
+                     Same as --dump-format plain",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("stack-format")
+                .long("stack-format")
+                .value_name("FORMAT")
+                .possible_values(&["decimal", "hex", "json", "chars"])
+                .default_value("decimal")
+                .help(
+                    "Sets how the stack is printed in diagnostics: as decimal or hexadecimal \
+                     numbers, as a JSON array, or as characters, with · for values that are \
+                     not printable ASCII",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("comment-char")
+                .long("comment-char")
+                .value_name("CHAR")
+                .help(
+                    "Removes source lines starting with CHAR before running. \
+                     This moves the lines below them up, changing their coordinates.",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("entry-from-label")
+                .long("entry-from-label")
+                .value_name("NAME")
+                .help(
+                    "Starts execution at the cell below the `;` of the line \";label NAME\". \
+                     Label lines are removed before running.",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("start-x")
+                .long("start-x")
+                .value_name("N")
+                .conflicts_with("entry-from-label")
+                .help("Starts the program counter in column N instead of 0"),
+        )
+        .arg(
+            clap::Arg::with_name("start-y")
+                .long("start-y")
+                .value_name("N")
+                .conflicts_with("entry-from-label")
+                .help("Starts the program counter in row N instead of 0"),
+        )
+        .arg(
+            clap::Arg::with_name("direction")
+                .long("direction")
+                .value_name("DIRECTION")
+                .possible_values(&["up", "down", "left", "right"])
+                .help("Starts the program counter moving in DIRECTION instead of right"),
+        )
+        .arg(
+            clap::Arg::with_name("output-events")
+                .long("output-events")
+                .value_name("PATH")
+                .help(
+                    "Writes each byte of output along with the step that wrote it to PATH, \
+                     one \"STEP BYTE\" pair per line",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("seed")
+                .long("seed")
+                .value_name("N")
+                .help("Seeds the random number generator used by ? for reproducible runs"),
+        )
+        .arg(
+            clap::Arg::with_name("seed-from-time")
+                .long("seed-from-time")
+                .conflicts_with("seed")
+                .help(
+                    "Seeds the random number generator used by ? from the current time \
+                     and prints the seed to stderr, so the run can be reproduced with --seed",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("split-input")
+                .long("split-input")
+                .help(
+                    "Reads the program's input from the passed file instead of stdin. \
+                     The program comes before the delimiter line and the input after it.",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("input-delimiter")
+                .long("input-delimiter")
+                .value_name("LINE")
+                .default_value("===")
+                .allow_hyphen_values(true)
+                .help("The line separating the program from its input with --split-input"),
+        )
+        .arg(
+            clap::Arg::with_name("compare-with")
+                .long("compare-with")
+                .value_name("BIN")
+                .conflicts_with("split-input")
+                .help(
+                    "Also runs the program with the reference interpreter BIN, giving it the \
+                     same input, and reports the first difference between the outputs",
+                ),
         )
-        .get_matches_safe()?;
+        .arg(
+            clap::Arg::with_name("max-steps")
+                .long("max-steps")
+                .value_name("N")
+                .help("Stops the program with an error if it has not halted after N steps"),
+        )
+        .arg(
+            clap::Arg::with_name("trace-tail")
+                .long("trace-tail")
+                .value_name("N")
+                .help(
+                    "Keeps a trace of the last N steps and prints it to stderr \
+                     if the program stops with an error",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("trace-json")
+                .long("trace-json")
+                .value_name("PATH")
+                .help(
+                    "Writes every executed step to PATH as JSON Lines, with the step, \
+                     position, cell, mode and stack after the step",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("limit-action")
+                .long("limit-action")
+                .value_name("ACTION")
+                .possible_values(&["error", "dump"])
+                .default_value("error")
+                .help(
+                    "What to do when --max-steps is reached: exit with an error (default), \
+                     or also print the stack, program counter and grid to stderr first",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("limit-output-lines")
+                .long("limit-output-lines")
+                .value_name("N")
+                .help("Halts the program once it has written N lines of output, like head"),
+        )
+        .arg(
+            clap::Arg::with_name("stats")
+                .long("stats")
+                .help("Prints the number of steps executed to stderr on exit"),
+        )
+        .arg(
+            clap::Arg::with_name("report-memory")
+                .long("report-memory")
+                .help("Prints an estimate of the memory used by the playfield to stderr after running"),
+        )
+        .arg(
+            clap::Arg::with_name("on-halt-print-stack")
+                .long("on-halt-print-stack")
+                .help("Prints the stack to stderr, top first, when the program halts without an error"),
+        )
+        .arg(
+            clap::Arg::with_name("dump-stack")
+                .long("dump-stack")
+                .help("Prints the stack to stderr on exit, bottom first, like [1, 2, 3]"),
+        )
+        .arg(
+            clap::Arg::with_name("cfg-dot")
+                .long("cfg-dot")
+                .value_name("PATH")
+                .help(
+                    "Writes an approximate control flow graph of the program to PATH \
+                     in Graphviz DOT format before running",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("show-config")
+                .long("show-config")
+                .help(
+                    "Prints the resolved program path, dialect, seed, limits and all other \
+                     options to stderr before running",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("annotate")
+                .long("annotate")
+                .help(
+                    "Prints the grid with the category of the command in each cell next to it, \
+                     instead of running the program",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("warn-unbalanced-quotes")
+                .long("warn-unbalanced-quotes")
+                .help(
+                    "Prints a warning to stderr before running if the program has an odd number \
+                     of \" characters, which may mean a string is never closed",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("echo-commands")
+                .long("echo-commands")
+                .help("Prints every executed command, except spaces, to stderr on a single line"),
+        )
+        .arg(
+            clap::Arg::with_name("warn-wrap")
+                .long("warn-wrap")
+                .help("Prints a warning to stderr every time the program counter wraps around an edge"),
+        )
+        .arg(
+            clap::Arg::with_name("extended")
+                .long("extended")
+                .help(
+                    "Enables commands outside of the Befunge-93 specification. \
+                     A pops expected and then actual, and errors unless they are equal",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("funge98-comments")
+                .long("funge98-comments")
+                .help("Makes ; skip every cell up to the next ;, as in Befunge-98"),
+        )
+        .arg(
+            clap::Arg::with_name("funge98-fetch")
+                .long("funge98-fetch")
+                .help("Makes ' push the next cell and skip over it, as in Befunge-98"),
+        )
+        .arg(
+            clap::Arg::with_name("funge98-iterate")
+                .long("funge98-iterate")
+                .help(
+                    "Makes k pop n and execute the next cell n times before skipping it, \
+                     as in Befunge-98",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("integer-cells")
+                .long("integer-cells")
+                .help(
+                    "Lets p store any integer in a cell and g fetch it unchanged, \
+                     as in Befunge-98, instead of only values 0-255",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("crlf")
+                .long("crlf")
+                .value_name("MODE")
+                .possible_values(&["pass-through", "coalesce"])
+                .help(
+                    "Writes a carriage return (13) followed by a line feed (10) as both bytes \
+                     (default), or coalesces the pair into just the line feed",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("alt-halt")
+                .long("alt-halt")
+                .value_name("CHAR")
+                .help(
+                    "Makes CHAR halt the program as well as @. Every cell holding CHAR \
+                     halts the program, so pick one the program never stores as data.",
+                ),
+        )
+        .get_matches_from_safe(args)?;
 
-    let resolved_filepath = resolve_filepath(matches.value_of("FILE").unwrap())?;
+    if matches.is_present("trace-json")
+        || matches.value_of("dump-format") == Some("json")
+        || matches.value_of("stack-format") == Some("json")
+    {
+        require_json_feature()?;
+    }
 
-    // Check if the file has a '.bf' or '.b93' extension
-    match resolved_filepath.extension() {
-        Some(extension) => {
-            if !(extension == "bf" || extension == "b93") {
-                return Err(
-                    "The file extension of the passed file was not '.bf' or '.b93'!".into(),
-                );
+    // With --eval, the program is passed directly instead of read from a file, and
+    // with a FILE of -, it is read from stdin
+    let (resolved_filepath, file_contents) = match matches.value_of("eval") {
+        Some(code) => (None, code.to_string()),
+        None if matches.value_of("FILE") == Some("-") => {
+            if matches.is_present("compare-with") {
+                return Err("--compare-with cannot be used with a program read from stdin".into());
             }
+
+            let mut file_contents = String::new();
+            io::stdin().lock().read_to_string(&mut file_contents)?;
+
+            (None, file_contents)
         }
-        None => return Err("The file extension of the passed file was not found!".into()),
+        None => {
+            let resolved_filepath = resolve_filepath(matches.value_of("FILE").unwrap())?;
+            let file_contents =
+                read_program_file(&resolved_filepath, !matches.is_present("force"))?;
+
+            (Some(resolved_filepath), file_contents)
+        }
+    };
+
+    // Program output goes to stdout unless redirected to a file with -o, which is
+    // truncated if it already exists. It is buffered, since the interpreter flushes
+    // it whenever the program stops or waits for input.
+    let mut output_handle: Box<dyn Write> = match matches.value_of("output") {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    // With --split-input, the program's input comes from the same file, and with -i,
+    // from another file instead of stdin. With --compare-with, all of the input is read
+    // up front so the reference interpreter can be given the same input.
+    let (code, input) = if matches.is_present("split-input") {
+        let delimiter = matches.value_of("input-delimiter").unwrap();
+        let (code, input) = split_program_and_input(&file_contents, delimiter)?;
+
+        (code, Some(input.into_bytes()))
+    } else if matches.is_present("compare-with") {
+        let mut input = Vec::new();
+        match matches.value_of("input") {
+            Some(path) => File::open(path)?.read_to_end(&mut input)?,
+            None => io::stdin().lock().read_to_end(&mut input)?,
+        };
+
+        (file_contents, Some(input))
+    } else {
+        (file_contents, None)
+    };
+    let mut input_handle: Box<dyn BufRead> = match (&input, matches.value_of("input")) {
+        (Some(input), _) => Box::new(Cursor::new(input.clone())),
+        (None, Some(path)) => Box::new(BufReader::new(File::open(path)?)),
+        (None, None) => Box::new(io::stdin().lock()),
+    };
+
+    let mut options = befunge::Options::default();
+    if matches.is_present("stack-capacity") {
+        options.stack_capacity = value_t!(matches, "stack-capacity", usize)?;
+    }
+    if let Some(path) = matches.value_of("stack-file") {
+        options.initial_stack = read_to_string(path)?
+            .split_whitespace()
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .map_err(|_| format!("{} in the stack file is not a valid integer!", value))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+    if let Some(mode) = matches.value_of("comparison") {
+        options.comparison = match mode {
+            "byte" => befunge::ComparisonMode::Byte,
+            _ => befunge::ComparisonMode::Signed,
+        };
+    }
+    if let Some(mode) = matches.value_of("division") {
+        options.division = match mode {
+            "floored" => befunge::DivisionMode::Floored,
+            _ => befunge::DivisionMode::Truncated,
+        };
+    }
+    if matches.is_present("floored-division") {
+        options.division = befunge::DivisionMode::Floored;
+    }
+    if matches.is_present("max-steps") {
+        options.max_steps = Some(value_t!(matches, "max-steps", u64)?);
+    }
+    if matches.is_present("max-put-writes") {
+        options.max_put_writes = Some(value_t!(matches, "max-put-writes", u64)?);
+    }
+    if matches.is_present("strict-halt") {
+        options.max_consecutive_spaces = Some(value_t!(matches, "strict-halt-limit", u64)?);
+    }
+    options.swap_pg_coords = matches.is_present("swap-pg-coords");
+    options.autoflush = matches.is_present("autoflush")
+        || (!matches.is_present("output") && io::stdout().is_terminal());
+    options.no_trailing_space = matches.is_present("no-trailing-space");
+    options.strict_ascii = matches.is_present("strict-ascii");
+    if matches.value_of("cell-range") == Some("ascii") {
+        options.cell_range = befunge::CellRange::Ascii;
+    }
+    if let Some(padding) = matches.value_of("pad-with") {
+        options.padding = match padding {
+            "null" => befunge::Padding::Null,
+            _ => befunge::Padding::Space,
+        };
+    }
+    if let Some(mode) = matches.value_of("char-input") {
+        options.char_input = match mode {
+            "ascii" => befunge::CharInputMode::Ascii,
+            "byte" => befunge::CharInputMode::Byte,
+            _ => befunge::CharInputMode::Unicode,
+        };
+    }
+    if matches.is_present("ascii-only-input") {
+        options.char_input = befunge::CharInputMode::SingleByte;
+    }
+    if let Some(separators) = matches.value_of("int-separators") {
+        options.int_separators = separators.chars().collect();
     }
+    if matches.is_present("comment-char") {
+        options.comment_char = Some(value_t!(matches, "comment-char", char)?);
+    }
+    options.entry_label = matches.value_of("entry-from-label").map(str::to_string);
+    if matches.is_present("limit-output-lines") {
+        options.max_output_lines = Some(value_t!(matches, "limit-output-lines", u64)?);
+    }
+    options.record_output_events =
+        matches.is_present("output-events") || matches.is_present("compare-with");
+    options.extended_commands = matches.is_present("extended");
+    options.funge98_comments = matches.is_present("funge98-comments");
+    options.funge98_fetch = matches.is_present("funge98-fetch");
+    options.funge98_iterate = matches.is_present("funge98-iterate");
+    options.integer_cells = matches.is_present("integer-cells");
+    if matches.value_of("crlf") == Some("coalesce") {
+        options.crlf = befunge::CrlfMode::Coalesce;
+    }
+    if matches.is_present("alt-halt") {
+        options.alt_halt = Some(value_t!(matches, "alt-halt", char)?);
+    }
+    // --show-config always prints the seed, so it picks one to make the run
+    // reproducible with --seed
+    let show_config = matches.is_present("show-config");
+    if matches.is_present("seed") {
+        options.seed = Some(value_t!(matches, "seed", u64)?);
+    } else if matches.is_present("seed-from-time") || show_config {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+        if !show_config {
+            eprintln!("Seed: {}", seed);
+        }
+
+        options.seed = Some(seed);
+    }
+
+    if show_config {
+        // The resolved path is always absolute
+        let program = match resolved_filepath {
+            Some(ref path) => path.display().to_string(),
+            None if matches.is_present("eval") => "(passed with --eval)".to_string(),
+            None => "(read from stdin)".to_string(),
+        };
 
-    let file_contents = read_to_string(resolved_filepath)?;
+        eprintln!("Program: {}", program);
+        eprintln!("Dialect: {}", dialect_name(&options));
+        eprintln!("Seed: {}", options.seed.unwrap());
+        eprintln!(
+            "Step limit: {}",
+            options
+                .max_steps
+                .map_or("none".to_string(), |limit| limit.to_string())
+        );
+        eprintln!("Options: {:#?}", options);
+    }
 
-    // TODO: Add support for redirected output to a file
-    let mut output_handle = io::stdout();
-    let input_handle = io::stdin();
-    let mut input_handle = input_handle.lock();
+    // A coordinate that is not given defaults to 0
+    let start_position = if matches.is_present("start-x") || matches.is_present("start-y") {
+        let mut position = befunge::Coord { x: 0, y: 0 };
+        if matches.is_present("start-x") {
+            position.x = value_t!(matches, "start-x", i64)?;
+        }
+        if matches.is_present("start-y") {
+            position.y = value_t!(matches, "start-y", i64)?;
+        }
+        Some(position)
+    } else {
+        None
+    };
+    let start_direction = matches
+        .value_of("direction")
+        .map(|direction| match direction {
+            "up" => befunge::Direction::Up,
+            "down" => befunge::Direction::Down,
+            "left" => befunge::Direction::Left,
+            _ => befunge::Direction::Right,
+        });
 
-    // TODO: Add support for user supplied initial direction and position
-    let mut interpreter = befunge::Interpreter::new(
-        &file_contents,
+    let mut interpreter = befunge::Interpreter::with_options(
+        &code,
         &mut output_handle,
         &mut input_handle,
-        None,
-        None,
+        start_position,
+        start_direction,
+        options,
     )?;
 
-    interpreter.execute()?;
+    if let Some(path) = matches.value_of("cfg-dot") {
+        let mut dot_file = File::create(path)?;
+        write_control_flow_dot(&mut dot_file, interpreter.playfield())?;
+    }
+
+    if matches.is_present("annotate") {
+        print!("{}", annotate_grid(interpreter.playfield())?);
+        return Ok(());
+    }
+
+    if matches.is_present("warn-unbalanced-quotes") {
+        let counts = interpreter.playfield().quote_counts();
+        let total = counts.iter().sum::<usize>();
+
+        if total % 2 == 1 {
+            let odd_rows = counts
+                .iter()
+                .enumerate()
+                .filter(|(_, &count)| count % 2 == 1)
+                .map(|(row, _)| row.to_string())
+                .collect::<Vec<_>>();
+
+            eprintln!(
+                "Warning: the program has an odd number of \" characters ({}), \
+                 so a string may never be closed. Rows with an odd count: {}",
+                total,
+                odd_rows.join(", ")
+            );
+        }
+    }
+
+    let result = run(&mut interpreter, &matches);
+
+    if matches.is_present("stats") {
+        eprintln!("Steps executed: {}", interpreter.steps());
+    }
+
+    if matches.is_present("report-memory") {
+        eprintln!(
+            "Playfield memory footprint: {} bytes",
+            interpreter.playfield().memory_footprint()
+        );
+    }
+
+    let stack_format = matches.value_of("stack-format").unwrap();
+    if result.is_ok() && matches.is_present("on-halt-print-stack") {
+        eprintln!(
+            "Stack (top first): {}",
+            format_stack(&interpreter, stack_format)
+        );
+    }
+
+    if matches.is_present("dump-stack") {
+        eprintln!("{}", format_stack_bottom_first(&interpreter));
+    }
+
+    let dump_format = if matches.is_present("dump-grid") {
+        Some("plain")
+    } else {
+        matches.value_of("dump-format")
+    };
+    if let Some(format) = dump_format {
+        let format = match format {
+            "ruled" => befunge::GridFormat::Ruled,
+            #[cfg(feature = "json")]
+            "json" => befunge::GridFormat::Json,
+            _ => befunge::GridFormat::Plain,
+        };
+        eprint!("{}", interpreter.playfield().render(format));
+    }
+
+    if let Some(path) = matches.value_of("save-stack") {
+        interpreter.save_stack(File::create(path)?)?;
+    }
+
+    if let Some(path) = matches.value_of("output-events") {
+        let mut events_file = File::create(path)?;
+        for (step, byte) in interpreter.output_events() {
+            writeln!(events_file, "{} {}", step, byte)?;
+        }
+    }
+
+    if let Err(err) = result {
+        if matches.is_present("verbose-errors") && err.is::<befunge::Error>() {
+            let playfield = interpreter.playfield();
+            let position = &playfield.program_counter_position;
+
+            eprint!(
+                "Grid around ({}, {}):\n{}",
+                position.x,
+                position.y,
+                playfield.render_excerpt(position, 2)
+            );
+        }
+        return Err(err);
+    }
+
+    // --compare-with conflicts with --eval, so there is always a file to pass on
+    if let (Some(reference), Some(path)) = (matches.value_of("compare-with"), &resolved_filepath) {
+        let output = interpreter
+            .output_events()
+            .iter()
+            .map(|&(_, byte)| byte)
+            .collect::<Vec<_>>();
+
+        compare_with_reference(reference, path, input.as_deref().unwrap_or(&[]), &output)?;
+    }
 
     Ok(())
 }
 
-// Resolves a passed filepath to either a relative or absolute location.
+// Reads the source code of a program from a file, which must have a '.bf', '.b93',
+// '.bef' or '.befunge' extension in any case unless `check_extension` is false,
+// optionally followed by '.gz' if it is gzip-compressed
+fn read_program_file(path: &Path, check_extension: bool) -> Result<String, Box<dyn error::Error>> {
+    // Gzip-compressed files are checked by the extension before the '.gz'
+    let is_gzipped = path.extension() == Some(OsStr::new("gz"));
+    let source_path = if is_gzipped {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    };
+
+    if check_extension {
+        match source_path.extension() {
+            Some(extension) => {
+                let extension = extension.to_string_lossy().to_lowercase();
+                if !["bf", "b93", "bef", "befunge"].contains(&extension.as_str()) {
+                    return Err(
+                        "The file extension of the passed file was not '.bf', '.b93', \
+                         '.bef' or '.befunge'!"
+                            .into(),
+                    );
+                }
+            }
+            None => return Err("The file extension of the passed file was not found!".into()),
+        }
+    }
+
+    if is_gzipped {
+        read_gzipped_to_string(path)
+    } else {
+        Ok(read_to_string(path)?)
+    }
+}
+
+// Runs the program with a reference interpreter, passing it the path of the program
+// and the input on stdin, and returns an error describing the first byte where its
+// output differs from the passed output
+fn compare_with_reference(
+    reference: &str,
+    program_path: &Path,
+    input: &[u8],
+    output: &[u8],
+) -> Result<(), Box<dyn error::Error>> {
+    let mut child = process::Command::new(reference)
+        .arg(program_path)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .spawn()?;
+
+    // Dropping stdin closes it, so the reference sees the end of the input
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+    let expected = child.wait_with_output()?.stdout;
+
+    match expected.iter().zip(output).position(|(a, b)| a != b) {
+        Some(index) => Err(format!(
+            "The output differs from the reference at byte {}: expected {}, but found {}!",
+            index, expected[index], output[index]
+        )
+        .into()),
+        None if expected.len() != output.len() => Err(format!(
+            "The output differs from the reference at byte {}: \
+             the reference wrote {} bytes, but this interpreter wrote {}!",
+            expected.len().min(output.len()),
+            expected.len(),
+            output.len()
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+// A single executed step, written as one line of the --trace-json file
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct TraceEvent {
+    step: u64,
+    #[serde(flatten)]
+    position: befunge::Coord,
+    #[serde(rename = "char")]
+    cell: char,
+    // The mode the cell was executed in
+    mode: &'static str,
+    // The stack after the step, from top to bottom
+    stack: Vec<i64>,
+}
+
+// Runs the interpreter until the program halts, printing any requested diagnostics
+// along the way. Diagnostics are written to stderr so they never mix with the
+// program's own output.
+fn run<Writable, Readable>(
+    interpreter: &mut befunge::Interpreter<Writable, Readable>,
+    matches: &clap::ArgMatches,
+) -> Result<(), Box<dyn error::Error>>
+where
+    Writable: Write,
+    Readable: BufRead,
+{
+    let progress_interval = if matches.is_present("progress") {
+        match value_t!(matches, "progress-interval", u64)? {
+            0 => return Err("The progress interval must be greater than 0!".into()),
+            interval => Some(interval),
+        }
+    } else {
+        None
+    };
+
+    let verbosity = matches.occurrences_of("verbose");
+    let stack_format = matches.value_of("stack-format").unwrap();
+    let echo_commands = matches.is_present("echo-commands");
+    let warn_wrap = matches.is_present("warn-wrap");
+
+    // On a terminal, progress updates overwrite each other on a single line
+    let stderr_is_terminal = io::stderr().is_terminal();
+
+    let dump_on_limit = matches.value_of("limit-action") == Some("dump");
+
+    // The trace entries of the last steps, which are only printed on an error
+    let trace_tail = if matches.is_present("trace-tail") {
+        Some(value_t!(matches, "trace-tail", usize)?)
+    } else {
+        None
+    };
+    let mut trace = VecDeque::new();
+
+    let mut trace_json = match matches.value_of("trace-json") {
+        Some(path) => Some(BufWriter::new(File::create(path)?)),
+        None => None,
+    };
+
+    let result = loop {
+        let steps_before = interpreter.steps();
+        let mode_before = interpreter.mode();
+        let playfield = interpreter.playfield();
+        let position = playfield.program_counter_position;
+        let direction_before = playfield.program_counter_direction;
+        let cell = if playfield.contains(&position) {
+            playfield.get_next_character()
+        } else {
+            ' '
+        };
+
+        let step_result = interpreter.step();
+
+        // Only log steps that executed a cell
+        if interpreter.steps() > steps_before {
+            if verbosity >= 3 || trace_tail.is_some() {
+                let entry = format!(
+                    "Step {}: {:?} at ({}, {}), stack (top first): {}",
+                    interpreter.steps(),
+                    cell,
+                    position.x,
+                    position.y,
+                    format_stack(interpreter, stack_format)
+                );
+
+                if verbosity >= 3 {
+                    eprintln!("{}", entry);
+                }
+
+                if let Some(length) = trace_tail {
+                    if trace.len() == length {
+                        trace.pop_front();
+                    }
+                    if length > 0 {
+                        trace.push_back(entry);
+                    }
+                }
+            }
+
+            if let Some(writer) = trace_json.as_mut() {
+                write_trace_event(writer, interpreter, position, cell, mode_before)?;
+            }
+
+            if verbosity >= 2 {
+                let mode = interpreter.mode();
+                if mode != mode_before {
+                    eprintln!(
+                        "Step {}: mode changed from {} to {} at ({}, {})",
+                        interpreter.steps(),
+                        mode_name(mode_before),
+                        mode_name(mode),
+                        position.x,
+                        position.y
+                    );
+                }
+
+                let direction = interpreter.playfield().program_counter_direction;
+                if direction != direction_before {
+                    eprintln!(
+                        "Step {}: direction changed from {} to {} at ({}, {})",
+                        interpreter.steps(),
+                        direction_name(direction_before),
+                        direction_name(direction),
+                        position.x,
+                        position.y
+                    );
+                }
+            }
+        }
+
+        if echo_commands {
+            match interpreter.last_command() {
+                Some(' ') | None => (),
+                Some(command) => eprint!("{}", command),
+            }
+        }
+
+        if warn_wrap && interpreter.wrapped() {
+            let playfield = interpreter.playfield();
+            let position = &playfield.program_counter_position;
+
+            eprintln!(
+                "Warning: the program counter wrapped around an edge moving {} to ({}, {})",
+                direction_name(playfield.program_counter_direction),
+                position.x,
+                position.y
+            );
+        }
+
+        match step_result {
+            Ok(befunge::StepResult::Continued) => (),
+            Ok(befunge::StepResult::Halted) => break Ok(()),
+            // Input always comes from stdin, so reading it blocks instead
+            Ok(befunge::StepResult::NeedsInput) => unreachable!(),
+            Err(err) => {
+                if dump_on_limit {
+                    if let Some(befunge::Error::StepLimitExceeded { .. }) = err.downcast_ref() {
+                        dump_state(interpreter, stack_format);
+                    }
+                }
+
+                break Err(err);
+            }
+        }
+
+        if let Some(interval) = progress_interval {
+            if interpreter.steps().is_multiple_of(interval) {
+                let position = &interpreter.playfield().program_counter_position;
+                let update = format!(
+                    "Step {}: program counter at ({}, {})",
+                    interpreter.steps(),
+                    position.x,
+                    position.y
+                );
+
+                if stderr_is_terminal {
+                    eprint!("\r{}", update);
+                } else {
+                    eprintln!("{}", update);
+                }
+            }
+        }
+    };
+
+    if let Some(mut writer) = trace_json {
+        writer.flush()?;
+    }
+
+    // End the echoed commands so later messages start on a fresh line
+    if echo_commands {
+        eprintln!();
+    }
+
+    // End the progress line so later messages start on a fresh line
+    if progress_interval.is_some() && stderr_is_terminal {
+        eprintln!();
+    }
+
+    if result.is_err() && trace_tail.is_some() {
+        eprintln!("Last {} steps before the error:", trace.len());
+        for entry in &trace {
+            eprintln!("{}", entry);
+        }
+    }
+
+    if verbosity >= 1 && result.is_ok() {
+        eprintln!(
+            "Halted after {} steps, writing {} bytes with a peak stack depth of {}",
+            interpreter.steps(),
+            interpreter.bytes_written(),
+            interpreter.peak_stack_depth()
+        );
+    }
+
+    result
+}
+
+// Prints the stack, program counter and grid of the interpreter to stderr
+fn dump_state<Writable, Readable>(
+    interpreter: &befunge::Interpreter<Writable, Readable>,
+    stack_format: &str,
+) where
+    Writable: Write,
+    Readable: BufRead,
+{
+    let playfield = interpreter.playfield();
+    let position = &playfield.program_counter_position;
+
+    eprintln!(
+        "Stack (top first): {}",
+        format_stack(interpreter, stack_format)
+    );
+    eprintln!(
+        "Program counter: ({}, {}) moving {}",
+        position.x,
+        position.y,
+        direction_name(playfield.program_counter_direction)
+    );
+    eprint!("Grid:\n{}", playfield.render(befunge::GridFormat::Plain));
+}
+
+// Renders every row of the grid next to a row of symbols giving the category of
+// each command in it, followed by a legend for the symbols. Spaces are left blank.
+fn annotate_grid(playfield: &befunge::Playfield) -> Result<String, Box<dyn error::Error>> {
+    let dimensions = &playfield.dimensions;
+    let rows = playfield.get_region(befunge::Coord { x: 0, y: 0 }, dimensions.x, dimensions.y)?;
+
+    let mut annotated = String::new();
+    for row in rows {
+        let code = row.iter().collect::<String>();
+        let symbols = row
+            .iter()
+            .map(|&command| match command {
+                ' ' => ' ',
+                _ => category_symbol(befunge::command_category(command)),
+            })
+            .collect::<String>();
+
+        annotated.push_str(&format!("{} | {}\n", code, symbols));
+    }
+    annotated.push_str(
+        "Legend: A arithmetic, S stack, M movement, C control, G memory, I input/output, \
+         ? not a command\n",
+    );
+
+    Ok(annotated)
+}
+
+// Returns the symbol used for a command category by --annotate
+fn category_symbol(category: befunge::CommandCategory) -> char {
+    match category {
+        befunge::CommandCategory::Arithmetic => 'A',
+        befunge::CommandCategory::Stack => 'S',
+        befunge::CommandCategory::Movement => 'M',
+        befunge::CommandCategory::Control => 'C',
+        befunge::CommandCategory::Memory => 'G',
+        befunge::CommandCategory::IO => 'I',
+        befunge::CommandCategory::Other => '?',
+    }
+}
+
+// Writes the control flow graph of the program from its initial program counter
+// in Graphviz DOT format. Nodes are named "x,y" and labeled with their command.
+fn write_control_flow_dot<W: Write>(
+    output: &mut W,
+    playfield: &befunge::Playfield,
+) -> Result<(), Box<dyn error::Error>> {
+    let start = &playfield.program_counter_position;
+    let direction = &playfield.program_counter_direction;
+
+    writeln!(output, "digraph befunge {{")?;
+    for cell in playfield.reachable_cells(start, direction) {
+        let label = match playfield.get_character_at(&cell)? {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            command => command.to_string(),
+        };
+
+        writeln!(
+            output,
+            "    \"{},{}\" [label=\"({}, {}) {}\"];",
+            cell.x, cell.y, cell.x, cell.y, label
+        )?;
+    }
+    for (from, to) in playfield.control_flow_edges(start, direction) {
+        writeln!(
+            output,
+            "    \"{},{}\" -> \"{},{}\";",
+            from.x, from.y, to.x, to.y
+        )?;
+    }
+    writeln!(output, "}}")?;
+
+    Ok(())
+}
+
+// Formats the values on the stack of the interpreter, top first, in one of the
+// formats accepted by --stack-format. The numeric formats separate values with
+// spaces, while chars joins them into a single string.
+fn format_stack<Writable, Readable>(
+    interpreter: &befunge::Interpreter<Writable, Readable>,
+    format: &str,
+) -> String
+where
+    Writable: Write,
+    Readable: BufRead,
+{
+    let values = interpreter.stack_top_first();
+
+    match format {
+        "hex" => values
+            .iter()
+            .map(|&value| {
+                if value < 0 {
+                    format!("-{:#x}", value.unsigned_abs())
+                } else {
+                    format!("{:#x}", value)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        #[cfg(feature = "json")]
+        "json" => serde_json::to_string(&values).unwrap(),
+        "chars" => values
+            .iter()
+            .map(|&value| match value {
+                32..=126 => value as u8 as char,
+                _ => '·',
+            })
+            .collect(),
+        _ => values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+// Formats the values on the stack of the interpreter like [1, 2, 3] for --dump-stack.
+// Unlike every other stack display this is bottom first, so the values read in the
+// order the program pushed them and in the same order --save-stack writes them.
+fn format_stack_bottom_first<Writable, Readable>(
+    interpreter: &befunge::Interpreter<Writable, Readable>,
+) -> String
+where
+    Writable: Write,
+    Readable: BufRead,
+{
+    let values = interpreter
+        .stack()
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>();
+
+    format!("[{}]", values.join(", "))
+}
+
+// Returns the name of a direction as used in diagnostics
+fn direction_name(direction: befunge::Direction) -> &'static str {
+    match direction {
+        befunge::Direction::Up => "up",
+        befunge::Direction::Down => "down",
+        befunge::Direction::Left => "left",
+        befunge::Direction::Right => "right",
+    }
+}
+
+// Returns a description of the dialect the options select, listing every extension
+// to Befunge-93 that is enabled
+fn dialect_name(options: &befunge::Options) -> String {
+    let extensions = [
+        (options.extended_commands, "extended commands"),
+        (options.funge98_comments, "Befunge-98 comments"),
+        (options.funge98_fetch, "Befunge-98 fetch"),
+        (options.funge98_iterate, "Befunge-98 iterate"),
+        (options.integer_cells, "integer cells"),
+    ]
+    .iter()
+    .filter(|&&(enabled, _)| enabled)
+    .map(|&(_, name)| name)
+    .collect::<Vec<_>>();
+
+    if extensions.is_empty() {
+        "Befunge-93".to_string()
+    } else {
+        format!("Befunge-93 with {}", extensions.join(", "))
+    }
+}
+
+// Returns the name of an interpreter mode as used in diagnostics
+fn mode_name(mode: befunge::Mode) -> &'static str {
+    match mode {
+        befunge::Mode::String => "string",
+        befunge::Mode::Command => "command",
+        befunge::Mode::Bridge => "bridge",
+        befunge::Mode::Comment => "comment",
+    }
+}
+
+// Splits the contents of a file into the program before the first line equal to
+// the delimiter, and the input after it
+fn split_program_and_input(
+    contents: &str,
+    delimiter: &str,
+) -> Result<(String, String), Box<dyn error::Error>> {
+    let mut offset = 0;
+
+    for line in contents.split_inclusive('\n') {
+        if line.trim_end_matches(&['\n', '\r'][..]) == delimiter {
+            let code = contents[..offset].trim_end_matches(&['\n', '\r'][..]);
+            let input = &contents[offset + line.len()..];
+
+            return Ok((code.to_string(), input.to_string()));
+        }
+        offset += line.len();
+    }
+
+    Err(format!("The input delimiter line '{}' was not found!", delimiter).into())
+}
+
+// Writes the step the interpreter just executed as one line of JSON
+#[cfg(feature = "json")]
+fn write_trace_event<Writable, Readable>(
+    writer: &mut impl Write,
+    interpreter: &befunge::Interpreter<Writable, Readable>,
+    position: befunge::Coord,
+    cell: char,
+    mode: befunge::Mode,
+) -> Result<(), Box<dyn error::Error>>
+where
+    Writable: Write,
+    Readable: BufRead,
+{
+    let event = TraceEvent {
+        step: interpreter.steps(),
+        position,
+        cell,
+        mode: mode_name(mode),
+        stack: interpreter.stack_top_first(),
+    };
+
+    serde_json::to_writer(&mut *writer, &event)?;
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+// Never called, since --trace-json is rejected by require_json_feature
+#[cfg(not(feature = "json"))]
+fn write_trace_event<Writable, Readable>(
+    _writer: &mut impl Write,
+    _interpreter: &befunge::Interpreter<Writable, Readable>,
+    _position: befunge::Coord,
+    _cell: char,
+    _mode: befunge::Mode,
+) -> Result<(), Box<dyn error::Error>>
+where
+    Writable: Write,
+    Readable: BufRead,
+{
+    Ok(())
+}
+
+// Checks that the JSON output options can be used
+#[cfg(feature = "json")]
+fn require_json_feature() -> Result<(), Box<dyn error::Error>> {
+    Ok(())
+}
+
+#[cfg(not(feature = "json"))]
+fn require_json_feature() -> Result<(), Box<dyn error::Error>> {
+    Err("JSON output requires bef93 to be built with the 'json' feature!".into())
+}
+
+// Reads and decompresses a gzip-compressed file
+#[cfg(feature = "flate2")]
+fn read_gzipped_to_string(path: &Path) -> Result<String, Box<dyn error::Error>> {
+    let mut contents = String::new();
+    GzDecoder::new(File::open(path)?).read_to_string(&mut contents)?;
+
+    Ok(contents)
+}
+
+#[cfg(not(feature = "flate2"))]
+fn read_gzipped_to_string(_path: &Path) -> Result<String, Box<dyn error::Error>> {
+    Err(
+        "Loading gzip-compressed files requires bef93 to be built with the 'flate2' feature!"
+            .into(),
+    )
+}
+
+// Resolves a passed filepath to the canonical, absolute location of the file.
 // If the file does not exist or refer to a file, a io::Error error will be returned.
 fn resolve_filepath(path: &str) -> Result<PathBuf, Box<dyn error::Error>> {
     let mut result = PathBuf::from(path);
@@ -138,5 +1553,128 @@ fn resolve_filepath(path: &str) -> Result<PathBuf, Box<dyn error::Error>> {
         }
     }
 
-    Ok(result)
+    // Resolve `.`, `..` and symlinks, so the extension is checked on the file that is
+    // actually read
+    Ok(canonicalize(result)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join(name)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_success() {
+        let result = run_cli(vec!["bef93".to_string(), fixture("empty.bf")]);
+
+        assert_eq!(result.exit_code, 0);
+        assert!(result.stdout.is_empty());
+        assert!(result.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_help() {
+        let result = run_cli(vec!["bef93", "--help"]);
+
+        assert_eq!(result.exit_code, 0);
+        assert!(result.stdout.contains("USAGE"));
+        assert!(result.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_flag() {
+        let result = run_cli(vec!["bef93", "--not-a-flag", "program.bf"]);
+
+        assert_eq!(result.exit_code, 1);
+        assert!(result.stdout.is_empty());
+        assert!(result.diagnostics.contains("--not-a-flag"));
+    }
+
+    #[test]
+    fn test_invalid_flag_value() {
+        let result = run_cli(vec![
+            "bef93".to_string(),
+            "--stack-capacity".to_string(),
+            "many".to_string(),
+            fixture("empty.bf"),
+        ]);
+
+        assert_eq!(result.exit_code, 1);
+        assert!(result.diagnostics.contains("many"));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let result = run_cli(vec!["bef93", "does_not_exist.bf"]);
+
+        assert_eq!(result.exit_code, 4);
+        assert_eq!(
+            result.diagnostics,
+            "IO Error: The passed path does not exist or does not refer to a file!\n"
+        );
+    }
+
+    #[test]
+    fn test_befunge_error() {
+        let result = run_cli(vec!["bef93".to_string(), fixture("divide_by_zero.bf")]);
+
+        assert_eq!(result.exit_code, 2);
+        assert_eq!(
+            result.diagnostics,
+            "Befunge-93 Error: Cannot divide 3 by 0!\n"
+        );
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let result = run_cli(vec![
+            "bef93".to_string(),
+            "--max-steps".to_string(),
+            "2".to_string(),
+            fixture("infinite_loop.bf"),
+        ]);
+
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(
+            result.diagnostics,
+            "Befunge-93 Error: The program did not halt within 2 steps!\n"
+        );
+    }
+
+    #[test]
+    fn test_resolve_filepath() {
+        let expected = current_dir()
+            .unwrap()
+            .join("tests")
+            .join("add.bf")
+            .canonicalize()
+            .unwrap();
+
+        assert_eq!(resolve_filepath("./tests/add.bf").unwrap(), expected);
+        assert_eq!(
+            resolve_filepath("tests/../tests/./add.bf").unwrap(),
+            expected
+        );
+        assert!(resolve_filepath("./tests/does_not_exist.bf").is_err());
+    }
+
+    #[test]
+    fn test_unknown_error() {
+        let result = run_cli(vec!["bef93".to_string(), fixture("stack.txt")]);
+
+        assert_eq!(result.exit_code, 1);
+        assert_eq!(
+            result.diagnostics,
+            "Unknown error: The file extension of the passed file was not '.bf', '.b93', \
+             '.bef' or '.befunge'!\n"
+        );
+    }
 }
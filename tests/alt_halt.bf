@@ -0,0 +1 @@
+"@",H1.@
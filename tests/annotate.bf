@@ -0,0 +1,2 @@
+>12+v
+@ .,<
@@ -0,0 +1,3 @@
+23+5Av
+v    <
+>23*7A@
@@ -0,0 +1,4 @@
+&>:1-:v v *_$.@
+ ^    _$>\:^
+===
+5
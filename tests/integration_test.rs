@@ -1,8 +1,12 @@
 extern crate assert_cmd;
+extern crate predicates;
+extern crate serde_json;
 
 use assert_cmd::prelude::*;
+use predicates::prelude::*;
 
-use std::env::current_dir;
+use std::env::{current_dir, temp_dir};
+use std::fs::{read_to_string, write};
 use std::process::Command;
 
 #[test]
@@ -53,12 +57,52 @@ fn test_example_file() {
     cmd.assert().success().code(0).stdout("Hello, World!\n");
 }
 
+#[test]
+fn test_stack_capacity() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--stack-capacity")
+        .arg("1024")
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert().success().code(0).stdout("Hello, World!\n");
+}
+
+#[test]
+fn test_empty_file() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg(current_dir().unwrap().join("tests").join("empty.bf"));
+
+    cmd.assert().success().code(0).stdout("");
+}
+
+#[test]
+fn test_program_from_stdin() {
+    // Unlike std::process::Command, this Command can write to the program's stdin
+    let mut cmd = assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("-").write_stdin(
+        read_to_string(current_dir().unwrap().join("tests").join("hello_world.bf")).unwrap(),
+    );
+
+    cmd.assert().success().code(0).stdout("Hello, World!\n");
+}
+
+#[test]
+fn test_program_from_stdin_with_input_file() {
+    let mut cmd = assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("-")
+        .arg("-i")
+        .arg(current_dir().unwrap().join("tests").join("numbers.txt"))
+        .write_stdin("&&+.@");
+
+    cmd.assert().success().code(0).stdout("42 ");
+}
+
 #[test]
 fn test_file_not_found() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
     cmd.arg("non_existent.bf");
 
-    cmd.assert().failure().code(1);
+    cmd.assert().failure().code(4);
 }
 
 #[test]
@@ -73,3 +117,957 @@ fn test_invalid_file_extension() {
 
     cmd.assert().failure().code(1);
 }
+
+#[test]
+fn test_upper_case_file_extension() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg(current_dir().unwrap().join("tests").join("upper_case.BF"));
+
+    cmd.assert().success().code(0).stdout("1 ");
+}
+
+#[test]
+fn test_unknown_file_extension() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg(current_dir().unwrap().join("tests").join("prog.txt"));
+
+    cmd.assert().failure().code(1).stdout("");
+}
+
+#[test]
+fn test_force_file_extension() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--force")
+        .arg(current_dir().unwrap().join("tests").join("prog.txt"));
+
+    cmd.assert().success().code(0).stdout("2 ");
+}
+
+#[test]
+fn test_verbose_errors() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--verbose-errors").arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("invalid_command.bf"),
+    );
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(" v       \n >  3 [x]\n"));
+}
+
+#[test]
+fn test_progress() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--progress")
+        .arg("--progress-interval")
+        .arg("25")
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("Hello, World!\n")
+        .stderr(
+            "Step 25: program counter at (19, 0)\n\
+             Step 50: program counter at (18, 0)\n\
+             Step 75: program counter at (19, 0)\n\
+             Step 100: program counter at (20, 0)\n\
+             Step 125: program counter at (21, 0)\n",
+        );
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_dump_format() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--dump-format")
+        .arg("json")
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("Hello, World!\n")
+        .stderr("{\"width\":24,\"height\":1,\"rows\":[\"64+\\\"!dlroW ,olleH\\\">:#,_@\"]}\n");
+}
+
+#[test]
+fn test_no_trailing_space() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--no-trailing-space").arg("--eval").arg("5.@");
+
+    cmd.assert().success().code(0).stdout("5");
+}
+
+#[test]
+fn test_stats() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--stats").arg("--eval").arg("5:.,@");
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("5 \u{5}")
+        .stderr("Steps executed: 5\n");
+}
+
+#[test]
+fn test_funge98_comments() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--funge98-comments")
+        .arg("--eval")
+        .arg(";ignored;5.@");
+
+    cmd.assert().success().code(0).stdout("5 ");
+}
+
+#[test]
+fn test_funge98_fetch() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--funge98-fetch").arg("--eval").arg("'A,@");
+
+    cmd.assert().success().code(0).stdout("A");
+}
+
+#[test]
+fn test_funge98_iterate() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--funge98-iterate").arg("--eval").arg("1233k.@");
+
+    cmd.assert().success().code(0).stdout("3 2 1 ");
+}
+
+#[test]
+fn test_dump_grid() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--dump-grid")
+        .arg(current_dir().unwrap().join("tests").join("self_modify.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("")
+        .stderr("\"X\"4Xp@\n");
+}
+
+#[test]
+fn test_output_events() {
+    let events_path = temp_dir().join("bef93_test_output_events.txt");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--output-events")
+        .arg(&events_path)
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert().success().code(0).stdout("Hello, World!\n");
+
+    let events = read_to_string(&events_path).unwrap();
+    let events = events.lines().collect::<Vec<_>>();
+
+    assert_eq!(events.len(), "Hello, World!\n".len());
+    assert_eq!(events.first(), Some(&"24 72"));
+    assert_eq!(events.last(), Some(&"128 10"));
+}
+
+#[test]
+fn test_start_position() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--start-y").arg("1").arg("--eval").arg("1.@\n2.@");
+
+    cmd.assert().success().code(0).stdout("2 ");
+}
+
+#[test]
+fn test_start_direction() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--start-x")
+        .arg("2")
+        .arg("--direction")
+        .arg("left")
+        .arg("--eval")
+        .arg("@.3");
+
+    cmd.assert().success().code(0).stdout("3 ");
+}
+
+#[test]
+fn test_start_position_out_of_bounds() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--start-x").arg("5").arg("--eval").arg("1.@");
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stdout("")
+        .stderr("Befunge-93 Error: Location (5, 0) is out of bounds!\n");
+}
+
+#[test]
+fn test_cell_range() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--cell-range")
+        .arg("ascii")
+        .arg("--eval")
+        .arg("88*2*,@");
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stdout("")
+        .stderr("Befunge-93 Error: 128 is not an ASCII value (between 0 and 127 inclusive)!\n");
+}
+
+#[test]
+fn test_truncated_division() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--eval").arg("07-2/.07-2%.@");
+
+    cmd.assert().success().code(0).stdout("-3 -1 ");
+}
+
+#[test]
+fn test_floored_division() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--floored-division")
+        .arg("--eval")
+        .arg("07-2/.07-2%.@");
+
+    cmd.assert().success().code(0).stdout("-4 1 ");
+}
+
+#[test]
+fn test_unicode_char_input() {
+    let mut cmd = assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--eval").arg("~.@").write_stdin("\u{e1}\n");
+
+    cmd.assert().success().code(0).stdout("225 ");
+}
+
+#[test]
+fn test_ascii_only_input() {
+    let mut cmd = assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--ascii-only-input")
+        .arg("--eval")
+        .arg("~.@")
+        .write_stdin("\u{e1}\n");
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stdout("")
+        .stderr("Befunge-93 Error: \u{e1} is not a valid single byte!\n");
+}
+
+#[test]
+fn test_ascii_only_input_raw_byte() {
+    let mut cmd = assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--ascii-only-input")
+        .arg("--eval")
+        .arg("~.@")
+        .write_stdin(&b"\xe9\n"[..]);
+
+    cmd.assert().success().code(0).stdout("233 ");
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_trace_json() {
+    let trace_path = temp_dir().join("bef93_test_trace.jsonl");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--trace-json")
+        .arg(&trace_path)
+        .arg("--eval")
+        .arg("12+.@");
+
+    cmd.assert().success().code(0).stdout("3 ");
+
+    let trace = read_to_string(&trace_path).unwrap();
+    let events = trace
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 5);
+    assert_eq!(
+        events[1],
+        serde_json::json!({
+            "step": 2,
+            "x": 1,
+            "y": 0,
+            "char": "2",
+            "mode": "command",
+            "stack": [2, 1]
+        })
+    );
+    assert_eq!(events[4]["char"], "@");
+}
+
+#[test]
+fn test_seed_from_time() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--seed-from-time").arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("random_digits.bf"),
+    );
+
+    let assert = cmd.assert().success().code(0);
+    let output = assert.get_output();
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+    let seed = stderr.trim().trim_start_matches("Seed: ");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--seed").arg(seed).arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("random_digits.bf"),
+    );
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout(output.stdout.clone())
+        .stderr("");
+}
+
+#[test]
+fn test_extended_assertions() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--extended")
+        .arg(current_dir().unwrap().join("tests").join("assertions.bf"));
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "Assertion failed! Expected 7, but found 6.",
+        ));
+}
+
+#[test]
+fn test_echo_commands() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--echo-commands")
+        .arg(current_dir().unwrap().join("tests").join("add.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("5 ")
+        .stderr("123+.@\n");
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn test_gzipped_file() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("hello_world.bf.gz"),
+    );
+
+    cmd.assert().success().code(0).stdout("Hello, World!\n");
+}
+
+#[test]
+fn test_warn_wrap() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--warn-wrap")
+        .arg(current_dir().unwrap().join("tests").join("wrap.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("5 ")
+        .stderr("Warning: the program counter wrapped around an edge moving left to (3, 0)\n");
+}
+
+#[test]
+fn test_max_steps() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--max-steps").arg("6").arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("infinite_loop.bf"),
+    );
+
+    cmd.assert()
+        .failure()
+        .code(3)
+        .stderr("Befunge-93 Error: The program did not halt within 6 steps!\n");
+}
+
+#[test]
+fn test_limit_action_dump() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--max-steps")
+        .arg("6")
+        .arg("--limit-action")
+        .arg("dump")
+        .arg(
+            current_dir()
+                .unwrap()
+                .join("tests")
+                .join("infinite_loop.bf"),
+        );
+
+    cmd.assert().failure().code(3).stderr(
+        "Stack (top first): 1\n\
+         Program counter: (0, 0) moving up\n\
+         Grid:\n\
+         >1v\n\
+         ^ <\n\
+         Befunge-93 Error: The program did not halt within 6 steps!\n",
+    );
+}
+
+#[test]
+fn test_split_input() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--split-input").arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("factorial_with_input.bf"),
+    );
+
+    cmd.assert().success().code(0).stdout("120 ");
+}
+
+#[test]
+fn test_split_input_missing_delimiter() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--split-input")
+        .arg("--input-delimiter")
+        .arg("---")
+        .arg(
+            current_dir()
+                .unwrap()
+                .join("tests")
+                .join("factorial_with_input.bf"),
+        );
+
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("'---' was not found"));
+}
+
+#[test]
+fn test_limit_output_lines() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--limit-output-lines")
+        .arg("3")
+        .arg(current_dir().unwrap().join("tests").join("count_lines.bf"));
+
+    cmd.assert().success().code(0).stdout("1 \n2 \n3 \n");
+}
+
+#[test]
+fn test_verbosity_levels() {
+    let run = |verbosity: &str| {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.arg(verbosity)
+            .arg(current_dir().unwrap().join("tests").join("wrap.bf"));
+
+        let assert = cmd.assert().success().code(0).stdout("5 ");
+        String::from_utf8(assert.get_output().stderr.clone()).unwrap()
+    };
+
+    let summary = "Halted after 4 steps, writing 2 bytes with a peak stack depth of 1\n";
+    let direction_change = "Step 1: direction changed from right to left at (0, 0)\n";
+
+    let level_one = run("-v");
+    assert_eq!(level_one, summary);
+
+    let level_two = run("-vv");
+    assert_eq!(level_two, format!("{}{}", direction_change, summary));
+
+    let level_three = run("-vvv");
+    assert!(level_three.contains(direction_change));
+    assert!(level_three.contains("Step 2: '5' at (3, 0), stack (top first): 5\n"));
+    assert!(level_three.ends_with(summary));
+    assert!(level_three.len() > level_two.len());
+}
+
+#[test]
+fn test_warn_unbalanced_quotes() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--warn-unbalanced-quotes").arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("unbalanced_quotes.bf"),
+    );
+
+    cmd.assert().success().code(0).stdout("a").stderr(
+        "Warning: the program has an odd number of \" characters (3), \
+         so a string may never be closed. Rows with an odd count: 1\n",
+    );
+}
+
+#[test]
+fn test_warn_unbalanced_quotes_balanced() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--warn-unbalanced-quotes")
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("Hello, World!\n")
+        .stderr("");
+}
+
+#[test]
+fn test_stack_file() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--stack-file")
+        .arg(current_dir().unwrap().join("tests").join("stack.txt"))
+        .arg(current_dir().unwrap().join("tests").join("sum.bf"));
+
+    cmd.assert().success().code(0).stdout("6 ");
+}
+
+#[test]
+fn test_on_halt_print_stack() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--on-halt-print-stack")
+        .arg(current_dir().unwrap().join("tests").join("leave_stack.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stderr("Stack (top first): 3 2 1\n");
+}
+
+#[test]
+fn test_dump_stack() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--dump-stack")
+        .arg(current_dir().unwrap().join("tests").join("leave_stack.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("")
+        .stderr("[1, 2, 3]\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--dump-stack")
+        .arg(current_dir().unwrap().join("tests").join("empty.bf"));
+
+    cmd.assert().success().code(0).stderr("[]\n");
+}
+
+#[test]
+fn test_stack_format_chars() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--on-halt-print-stack")
+        .arg("--stack-format")
+        .arg("chars")
+        .arg(current_dir().unwrap().join("tests").join("string_stack.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stderr("Stack (top first): hello·\n");
+}
+
+#[test]
+fn test_on_halt_print_stack_error() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--on-halt-print-stack").arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("invalid_command.bf"),
+    );
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("Stack").not());
+}
+
+#[test]
+fn test_report_memory() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--report-memory")
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("Hello, World!\n")
+        .stderr(predicate::str::is_match("^Playfield memory footprint: [0-9]+ bytes\n$").unwrap());
+}
+
+#[test]
+fn test_cfg_dot() {
+    let dot_path = temp_dir().join("bef93_test_cfg.dot");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--cfg-dot")
+        .arg(&dot_path)
+        .arg(current_dir().unwrap().join("tests").join("wrap.bf"));
+
+    cmd.assert().success().code(0).stdout("5 ");
+
+    assert_eq!(
+        read_to_string(&dot_path).unwrap(),
+        "digraph befunge {\n    \
+         \"0,0\" [label=\"(0, 0) <\"];\n    \
+         \"1,0\" [label=\"(1, 0) @\"];\n    \
+         \"2,0\" [label=\"(2, 0) .\"];\n    \
+         \"3,0\" [label=\"(3, 0) 5\"];\n    \
+         \"0,0\" -> \"3,0\";\n    \
+         \"2,0\" -> \"1,0\";\n    \
+         \"3,0\" -> \"2,0\";\n\
+         }\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_compare_with() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--compare-with")
+        .arg(
+            current_dir()
+                .unwrap()
+                .join("tests")
+                .join("reference_stub.sh"),
+        )
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("Hello, World!\n")
+        .stderr("");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_compare_with_mismatch() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--compare-with")
+        .arg(
+            current_dir()
+                .unwrap()
+                .join("tests")
+                .join("reference_mismatch.sh"),
+        )
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "The output differs from the reference at byte 7: expected 119, but found 87!",
+        ));
+}
+
+#[test]
+fn test_trace_tail_on_error() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--trace-tail").arg("2").arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("divide_by_zero.bf"),
+    );
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "Last 2 steps before the error:\n\
+             Step 4: '0' at (3, 0), stack (top first): 0 3\n\
+             Step 5: '/' at (4, 0), stack (top first): \n",
+        ))
+        .stderr(predicate::str::contains("'+'").not());
+}
+
+#[test]
+fn test_trace_tail_on_success() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--trace-tail")
+        .arg("2")
+        .arg(current_dir().unwrap().join("tests").join("add.bf"));
+
+    cmd.assert().success().code(0).stdout("5 ").stderr("");
+}
+
+#[test]
+fn test_entry_from_label() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--entry-from-label")
+        .arg("start")
+        .arg(current_dir().unwrap().join("tests").join("labels.bf"));
+
+    cmd.assert().success().code(0).stdout("2 ").stderr("");
+}
+
+#[test]
+fn test_alt_halt() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--alt-halt")
+        .arg("H")
+        .arg(current_dir().unwrap().join("tests").join("alt_halt.bf"));
+
+    cmd.assert().success().code(0).stdout("@").stderr("");
+}
+
+#[test]
+fn test_max_put_writes() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--max-put-writes")
+        .arg("3")
+        .arg(current_dir().unwrap().join("tests").join("put_loop.bf"));
+
+    cmd.assert()
+        .failure()
+        .code(3)
+        .stderr("Befunge-93 Error: More than 3 put (p) commands were executed!\n");
+}
+
+#[test]
+fn test_save_and_load_stack() {
+    let stack_path = temp_dir().join("bef93_test_save_stack.txt");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--save-stack")
+        .arg(&stack_path)
+        .arg(current_dir().unwrap().join("tests").join("leave_stack.bf"));
+
+    cmd.assert().success().code(0).stdout("");
+    assert_eq!(read_to_string(&stack_path).unwrap(), "1\n2\n3\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--load-stack")
+        .arg(&stack_path)
+        .arg(current_dir().unwrap().join("tests").join("sum.bf"));
+
+    cmd.assert().success().code(0).stdout("6 ");
+}
+
+#[test]
+fn test_output_file() {
+    let output_path = temp_dir().join("bef93_test_output.txt");
+    write(
+        &output_path,
+        "Previous contents that are longer than the output",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("-o")
+        .arg(&output_path)
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert().success().code(0).stdout("").stderr("");
+    assert_eq!(read_to_string(&output_path).unwrap(), "Hello, World!\n");
+}
+
+#[test]
+fn test_autoflush() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--autoflush")
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("Hello, World!\n")
+        .stderr("");
+}
+
+#[test]
+fn test_output_file_error() {
+    let output_path = temp_dir()
+        .join("bef93_missing_directory")
+        .join("output.txt");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--output")
+        .arg(&output_path)
+        .arg(current_dir().unwrap().join("tests").join("hello_world.bf"));
+
+    cmd.assert()
+        .failure()
+        .code(4)
+        .stdout("")
+        .stderr(predicate::str::starts_with("IO Error: "));
+}
+
+#[test]
+fn test_input_file() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("-i")
+        .arg(current_dir().unwrap().join("tests").join("numbers.txt"))
+        .arg(current_dir().unwrap().join("tests").join("add_input.bf"));
+
+    cmd.assert().success().code(0).stdout("42 ").stderr("");
+}
+
+#[test]
+fn test_eval() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("-e").arg("64+\"!dlroW ,olleH\">:#,_@");
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("Hello, World!\n")
+        .stderr("");
+}
+
+#[test]
+fn test_compare_with_split_input() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--compare-with")
+        .arg("bef")
+        .arg("--split-input")
+        .arg(current_dir().unwrap().join("tests").join("add.bf"));
+
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_eval_with_file() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--eval")
+        .arg("1.@")
+        .arg(current_dir().unwrap().join("tests").join("add.bf"));
+
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_befunge_error_exit_code() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("divide_by_zero.bf"),
+    );
+
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr("Befunge-93 Error: Cannot divide 3 by 0!\n");
+}
+
+#[test]
+fn test_step_limit_exit_code() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--max-steps").arg("100").arg(
+        current_dir()
+            .unwrap()
+            .join("tests")
+            .join("infinite_loop.bf"),
+    );
+
+    cmd.assert()
+        .failure()
+        .code(3)
+        .stderr("Befunge-93 Error: The program did not halt within 100 steps!\n");
+}
+
+#[test]
+fn test_annotate() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--annotate")
+        .arg(current_dir().unwrap().join("tests").join("annotate.bf"));
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout(
+            ">12+v | MSSAM\n\
+             @ .,< | C IIM\n\
+             Legend: A arithmetic, S stack, M movement, C control, G memory, \
+             I input/output, ? not a command\n",
+        )
+        .stderr("");
+}
+
+#[test]
+fn test_show_config() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.current_dir(current_dir().unwrap().join("tests"))
+        .arg("--show-config")
+        .arg("add.bf");
+
+    let path = current_dir().unwrap().join("tests").join("add.bf");
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stdout("5 ")
+        .stderr(predicate::str::contains(format!(
+            "Program: {}\nDialect: Befunge-93\nSeed: ",
+            path.display()
+        )))
+        .stderr(predicate::str::contains("Seed: random").not())
+        .stderr(predicate::str::contains("Options: Options {"));
+}
+
+#[test]
+fn test_show_config_dialect_and_seed() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--show-config")
+        .arg("--seed")
+        .arg("7")
+        .arg("--funge98-comments")
+        .arg("--integer-cells")
+        .arg("--eval")
+        .arg("@");
+
+    cmd.assert()
+        .success()
+        .code(0)
+        .stderr(predicate::str::contains(
+            "Program: (passed with --eval)\n\
+         Dialect: Befunge-93 with Befunge-98 comments, integer cells\n\
+         Seed: 7\n",
+        ));
+}
+
+#[test]
+fn test_seed() {
+    let run = || {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.arg("--seed").arg("1234").arg(
+            current_dir()
+                .unwrap()
+                .join("tests")
+                .join("random_digits.bf"),
+        );
+
+        cmd.assert().success().code(0).get_output().stdout.clone()
+    };
+
+    assert_eq!(run(), run());
+}
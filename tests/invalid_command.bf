@@ -0,0 +1,2 @@
+12v
+  >3x
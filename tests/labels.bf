@@ -0,0 +1,3 @@
+1.@
+ ;label start
+ >2.@
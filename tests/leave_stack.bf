@@ -0,0 +1 @@
+123@
@@ -0,0 +1,2 @@
+v
+>"v"00p
@@ -0,0 +1,6 @@
+55+>:!#@_1-v
+
+        v.1?2.v
+           3
+           .
+   ^    <  <  <
@@ -0,0 +1 @@
+"X"40p@